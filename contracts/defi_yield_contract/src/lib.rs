@@ -1,4 +1,22 @@
-use soroban_sdk::{contract, contractimpl, Address, Env, Map, Symbol, token, Vec};
+use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, IntoVal, Map, Symbol, token, Vec};
+
+/// Feed categories of the Aureus oracle
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeedKind {
+    Cpi,
+    Fx,
+    Asset,
+}
+
+/// Feed value returned by the Aureus oracle's `read_premium`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeedValue {
+    pub value: i128,
+    pub timestamp: u64,
+    pub sequence: u32,
+}
 
 #[contract]
 pub struct DeFiYieldContract;
@@ -107,11 +125,14 @@ impl DeFiYieldContract {
 
     /// Automate allocation based on oracle
     pub fn auto_allocate(env: Env, user: Address, oracle: Address, country: Symbol) {
-        let cpi: i128 = env.invoke_contract(
-            &oracle,
-            &Symbol::new(&env, "get_cpi"),
-            (country,).into_val(&env),
-        );
+        // Get CPI through read_premium
+        let cpi = env
+            .invoke_contract::<FeedValue>(
+                &oracle,
+                &Symbol::new(&env, "read_premium"),
+                (env.current_contract_address(), FeedKind::Cpi, country).into_val(&env),
+            )
+            .value;
 
         if cpi < 150 { // Low inflation, focus on yield
             // Allocate more to yield farming
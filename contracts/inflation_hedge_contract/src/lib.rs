@@ -1,4 +1,22 @@
-use soroban_sdk::{contract, contractimpl, Address, Env, Map, Symbol, token, Vec};
+use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, IntoVal, Map, Symbol, token, Vec};
+
+/// Feed categories of the Aureus oracle
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeedKind {
+    Cpi,
+    Fx,
+    Asset,
+}
+
+/// Feed value returned by the Aureus oracle's `read_premium`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeedValue {
+    pub value: i128,
+    pub timestamp: u64,
+    pub sequence: u32,
+}
 
 #[contract]
 pub struct InflationHedgeContract;
@@ -51,11 +69,14 @@ impl InflationHedgeContract {
     /// Rebalance allocations based on oracle data
     pub fn rebalance(env: Env, user: Address, country: Symbol) {
         let oracle: Address = env.storage().instance().get(&Symbol::new(&env, "oracle")).unwrap();
-        let cpi: i128 = env.invoke_contract(
-            &oracle,
-            &Symbol::new(&env, "get_cpi"),
-            (country,).into_val(&env),
-        );
+        // Get CPI through read_premium
+        let cpi = env
+            .invoke_contract::<FeedValue>(
+                &oracle,
+                &Symbol::new(&env, "read_premium"),
+                (env.current_contract_address(), FeedKind::Cpi, country).into_val(&env),
+            )
+            .value;
 
         let mut allocations: Map<Address, Map<Symbol, i128>> = env
             .storage()
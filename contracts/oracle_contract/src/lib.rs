@@ -1,142 +1,4458 @@
-use soroban_sdk::{contract, contractimpl, Env, Map, Symbol, Val};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, panic_with_error, token, xdr::ToXdr, Address,
+    BytesN, Env, IntoVal, Map, String, Symbol, SymbolStr, TryFromVal, Val, Vec,
+};
+
+mod math;
+mod storage;
+
+use math::{mul_div, WAD};
+
+/// Category of data published by a feed
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeedKind {
+    Cpi,
+    Fx,
+    Asset,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    /// No value was published within the requested age
+    Stale = 1,
+    AlreadyInitialized = 2,
+    /// The contract, or the fee settings a call needs, have not been set up
+    NotInitialized = 3,
+    /// The reporter may not write the feed, or the caller must pay to read it
+    Unauthorized = 4,
+    /// No value has been published for the feed
+    FeedNotFound = 5,
+    ReporterExists = 6,
+    ReporterNotFound = 7,
+    InvalidQuorum = 8,
+    /// The reporter already submitted a value in the open round
+    AlreadySubmitted = 9,
+    /// The period is not a valid YYYYMM value
+    InvalidPeriod = 10,
+    /// The value is outside the range accepted for the feed
+    InvalidValue = 11,
+    /// The history or observation limit is out of range
+    InvalidHistoryLimit = 12,
+    /// No CPI index is recorded for the period
+    PeriodNotFound = 13,
+    /// The observation buffer does not cover the requested window
+    InsufficientHistory = 14,
+    InvalidWindow = 15,
+    /// The SEP-40 price configuration has not been set
+    PriceConfigNotSet = 17,
+    /// The feed has no registered metadata
+    FeedNotRegistered = 18,
+    InvalidDecimals = 19,
+    /// The feed's circuit breaker tripped and awaits an admin decision
+    FeedTripped = 20,
+    /// The feed has no value held by the circuit breaker
+    NoPendingValue = 21,
+    /// The signing key is not registered to a reporter
+    UnknownKey = 22,
+    /// The report nonce was already used or is lower than the last accepted one
+    InvalidNonce = 23,
+    /// The report timestamp is in the future or too old
+    InvalidTimestamp = 24,
+    /// Optimistic CPI proposals have not been configured
+    OptimisticNotConfigured = 25,
+    InvalidBond = 26,
+    /// The country already has an open proposal
+    ProposalExists = 27,
+    ProposalNotFound = 28,
+    /// The proposal's dispute window has closed
+    DisputeWindowClosed = 29,
+    /// The proposal's dispute window is still open
+    DisputeWindowOpen = 30,
+    /// The proposal is disputed and awaits the arbiter
+    ProposalDisputed = 31,
+    /// The proposal is not disputed
+    NotDisputed = 32,
+    /// Reporter staking has not been configured
+    StakeNotConfigured = 33,
+    /// The reporter's stake is below the configured minimum
+    InsufficientStake = 34,
+    InvalidAmount = 35,
+    /// The FX base currency has not been configured
+    FxBaseNotSet = 36,
+    /// No published pair connects the currencies directly, inversely or through the base currency
+    NoFxPath = 37,
+    /// The currency code cannot form a pair symbol
+    InvalidCurrency = 38,
+    /// The cross rate does not fit in fixed-point range
+    Overflow = 39,
+    /// The feed has the maximum number of subscribers
+    TooManySubscribers = 40,
+    SubscriptionNotFound = 41,
+    /// More fallback oracles than allowed
+    TooManyFallbacks = 42,
+    /// Values must be committed and revealed while commit-reveal rounds are enabled
+    CommitRevealRequired = 43,
+    /// The feed's commit phase has ended
+    CommitPhaseClosed = 44,
+    /// The feed's commit round is not in its reveal phase
+    NotRevealPhase = 45,
+    /// The revealed value and salt do not match the commitment
+    InvalidReveal = 46,
+    /// Commit-reveal rounds are disabled or the feed has no open round
+    NoCommitRound = 47,
+    /// The feed's reveal phase has not ended
+    RevealPhaseOpen = 48,
+    /// The feed is paused by the admin
+    FeedPaused = 49,
+    /// The feed is retired; `get_successor` names its replacement, if any
+    FeedRetired = 50,
+    /// The value is further from the previous one than the feed's max deviation
+    DeviationTooLarge = 51,
+}
+
+/// A published feed value and the ledger it was published in
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeedValue {
+    pub value: i128,
+    pub timestamp: u64,
+    pub sequence: u32,
+}
+
+/// A price observation; `cumulative` is the time-weighted sum of all prior prices up to `timestamp`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Observation {
+    pub timestamp: u64,
+    pub price: i128,
+    pub cumulative: i128,
+}
+
+/// SEP-40 asset identifier
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
+
+/// SEP-40 price record
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+/// SEP-40 settings shared by all asset feeds
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceConfig {
+    pub base: Asset,
+    pub decimals: u32,
+    pub resolution: u32,
+}
+
+/// Lifecycle state of a feed
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeedStatus {
+    Active,
+    /// Neither accepts nor serves values until resumed
+    Paused,
+    /// Still served while consumers move to the successor
+    Deprecated,
+    /// Permanently closed
+    Retired,
+}
+
+/// Token premium read fees are paid in and the treasury they are withdrawn to
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeConfig {
+    pub token: Address,
+    pub treasury: Address,
+}
+
+/// Liveness of a published or registered feed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeedHealth {
+    pub kind: FeedKind,
+    pub feed: Symbol,
+    /// Whether the feed is registered but has never published a value
+    pub never_updated: bool,
+    /// Seconds since the last published value; `u64::MAX` if the feed never published
+    pub age: u64,
+    /// Heartbeats elapsed since the last published value; zero without a registered heartbeat
+    pub missed_heartbeats: u64,
+    /// Reporters whose values formed the last published round
+    pub last_participants: u32,
+    /// Reporters who have submitted to the open round
+    pub open_submissions: u32,
+    pub tripped: bool,
+    pub status: FeedStatus,
+}
+
+/// Declared scale, units and provenance of a feed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeedMetadata {
+    /// Fixed-point decimals of submitted values
+    pub decimals: u32,
+    pub base: Symbol,
+    pub quote: Symbol,
+    /// Human-readable unit, e.g. "CPI index, 2010=100"
+    pub unit: String,
+    /// Identifier of the upstream data source
+    pub source: String,
+    /// Expected seconds between updates
+    pub heartbeat: u64,
+}
+
+/// Reporter submissions collected for a feed until quorum is reached
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Round {
+    pub id: u32,
+    pub submissions: Map<Address, i128>,
+    /// Ledger timestamp of the round's first submission
+    pub opened_at: u64,
+    /// Observation time of the round's oldest submission, which dates the published value
+    pub observed_at: u64,
+}
+
+/// Settings of optimistic CPI proposals
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OptimisticConfig {
+    /// Token bonds are posted in
+    pub token: Address,
+    /// Bond posted by proposers, and matched by challengers
+    pub bond: i128,
+    /// Seconds a proposal can be disputed for
+    pub dispute_window: u64,
+    /// Account that resolves disputes
+    pub arbiter: Address,
+}
+
+/// A bonded CPI value that becomes final unless disputed within the dispute window
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposal {
+    pub proposer: Address,
+    pub value: i128,
+    pub bond: i128,
+    pub token: Address,
+    pub proposed_at: u64,
+    pub dispute_window: u64,
+    /// Whether a challenger posted a counter-bond; see `get_challenger`
+    pub disputed: bool,
+}
+
+/// Settings of reporter staking
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StakeConfig {
+    /// Token stakes are posted in
+    pub token: Address,
+    /// Stake a reporter needs to submit values
+    pub min_stake: i128,
+    /// Distance from the round median, in basis points, past which a submission is slashed; zero disables
+    pub outlier_bps: u32,
+    /// Amount slashed from an outlier submission
+    pub outlier_slash: i128,
+    /// Seconds unstaked tokens stay slashable before they can be withdrawn
+    pub unbonding_period: u64,
+}
+
+/// Stake a reporter has withdrawn and can claim once unbonded
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unbonding {
+    /// Tokens still unbonding
+    pub amount: i128,
+    /// Ledger timestamp from which the tokens can be withdrawn
+    pub available_at: u64,
+}
+
+/// Base currency FX cross rates are triangulated through, and the fixed-point decimals of FX rates
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FxConfig {
+    pub base: Symbol,
+    pub decimals: u32,
+}
+
+/// Condition under which a subscriber is notified of a feed update
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Trigger {
+    /// Every published value
+    Any,
+    /// A value on the other side of the threshold from the previous one
+    Threshold(i128),
+    /// A value that moved by at least this many basis points since the last notification
+    Deviation(u32),
+}
+
+/// A consumer contract notified through `on_oracle_update(feed, value)` when its trigger fires
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Subscription {
+    pub consumer: Address,
+    pub trigger: Trigger,
+    /// Value the consumer was last notified of; zero before the first notification
+    pub last_notified: i128,
+}
+
+/// Another oracle consulted when a feed is missing or stale
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fallback {
+    /// An Aureus oracle, read through `get_many`
+    Aureus(Address),
+    /// A SEP-40 oracle, read through `lastprice` for asset feeds
+    Sep40(Address),
+}
+
+/// A feed value and the oracle that answered it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SourcedValue {
+    pub value: i128,
+    pub timestamp: u64,
+    /// This oracle, or the fallback oracle the value came from
+    pub source: Address,
+}
+
+/// Phase lengths of commit-reveal rounds and the stake lost by reporters who do not reveal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitRevealConfig {
+    pub commit_period: u64,
+    pub reveal_period: u64,
+    pub penalty: i128,
+}
+
+/// Hashed reporter values for a feed, revealed once the commit phase ends
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitRound {
+    pub id: u32,
+    pub commit_end: u64,
+    pub reveal_end: u64,
+    /// SHA-256 of the XDR of `(reporter, kind, feed, id, value, salt)` per reporter
+    pub commits: Map<Address, BytesN<32>>,
+    pub reveals: Map<Address, i128>,
+}
+
+impl Round {
+    pub(crate) fn new(env: &Env, id: u32) -> Self {
+        Round {
+            id,
+            submissions: Map::new(env),
+            opened_at: 0,
+            observed_at: 0,
+        }
+    }
+}
+
 
 #[contract]
 pub struct OracleContract;
 
 #[contractimpl]
 impl OracleContract {
-    /// Set CPI inflation rate for a country
-    pub fn set_cpi(env: Env, country: Symbol, cpi: i128) {
-        let mut cpi_data: Map<Symbol, i128> = env
-            .storage()
-            .persistent()
-            .get(&Symbol::new(&env, "cpi"))
-            .unwrap_or(Map::new(&env));
+    /// Initialize the oracle with the admin that manages reporters. The admin must authorize it.
+    pub fn init(env: Env, admin: Address) {
+        admin.require_auth();
+
+        if storage::has_admin(&env) {
+            panic_with_error!(&env, Error::AlreadyInitialized);
+        }
+
+        storage::write_admin(&env, &admin);
+
+        // State left by the single-map layout is brought up to date by `migrate`
+        let version = if storage::has_legacy_values(&env) { 1 } else { CONTRACT_VERSION };
+        storage::write_version(&env, version);
+    }
+
+    /// Get the oracle admin
+    pub fn get_admin(env: Env) -> Address {
+        storage::read_admin(&env)
+    }
+
+    /// Get the storage version the contract's state was last migrated to
+    pub fn version(env: Env) -> u32 {
+        storage::read_version(&env)
+    }
+
+    /// Replace the contract's code, keeping its address and state (admin only).
+    /// Call `migrate` afterwards to bring state up to the new code's version.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) {
+        require_admin(&env);
+
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+
+        env.events().publish(
+            (Symbol::new(&env, "upgrade"),),
+            (storage::read_version(&env), new_wasm_hash),
+        );
+    }
+
+    /// Run the state migrations between the stored version and this code's version (admin only).
+    /// Returns the resulting version; does nothing when state is already current.
+    pub fn migrate(env: Env) -> u32 {
+        require_admin(&env);
+
+        let from = storage::read_version(&env);
+        if from >= CONTRACT_VERSION {
+            return from;
+        }
+
+        if from < 2 {
+            storage::migrate_legacy_values(&env);
+        }
+        storage::write_version(&env, CONTRACT_VERSION);
+
+        env.events().publish(
+            (Symbol::new(&env, "migrate"),),
+            (from, CONTRACT_VERSION),
+        );
 
-        cpi_data.set(country.clone(), cpi);
+        CONTRACT_VERSION
+    }
+
+    /// Register a reporter (admin only). New reporters have no feed permissions.
+    pub fn add_reporter(env: Env, reporter: Address) {
+        require_admin(&env);
 
-        env.storage()
-            .persistent()
-            .set(&Symbol::new(&env, "cpi"), &cpi_data);
+        if storage::read_reporter_feeds(&env, &reporter).is_some() {
+            panic_with_error!(&env, Error::ReporterExists);
+        }
+
+        storage::write_reporter_feeds(&env, &reporter, &Vec::new(&env));
 
         env.events().publish(
-            (Symbol::new(&env, "set_cpi"), country),
-            cpi,
+            (Symbol::new(&env, "add_reporter"), reporter),
+            (),
         );
     }
 
-    /// Get CPI for a country
-    pub fn get_cpi(env: Env, country: Symbol) -> i128 {
-        let cpi_data: Map<Symbol, i128> = env
-            .storage()
-            .persistent()
-            .get(&Symbol::new(&env, "cpi"))
-            .unwrap_or(Map::new(&env));
+    /// Remove a reporter and all of its feed permissions (admin only)
+    pub fn remove_reporter(env: Env, reporter: Address) {
+        require_admin(&env);
+
+        if storage::read_reporter_feeds(&env, &reporter).is_none() {
+            panic_with_error!(&env, Error::ReporterNotFound);
+        }
 
-        cpi_data.get(country).unwrap_or(0)
+        storage::remove_reporter(&env, &reporter);
+        storage::remove_reporter_key(&env, &reporter);
+
+        env.events().publish(
+            (Symbol::new(&env, "remove_reporter"), reporter),
+            (),
+        );
     }
 
-    /// Set FX rate for a currency pair
-    pub fn set_fx(env: Env, pair: Symbol, rate: i128) {
-        let mut fx_data: Map<Symbol, i128> = env
-            .storage()
-            .persistent()
-            .get(&Symbol::new(&env, "fx"))
-            .unwrap_or(Map::new(&env));
+    /// Allow a reporter to write a feed (admin only)
+    pub fn allow_feed(env: Env, reporter: Address, kind: FeedKind, feed: Symbol) {
+        require_admin(&env);
+
+        let mut feeds = read_registered_reporter(&env, &reporter);
+        if !feeds.contains((kind, feed.clone())) {
+            feeds.push_back((kind, feed.clone()));
+            storage::write_reporter_feeds(&env, &reporter, &feeds);
+        }
 
-        fx_data.set(pair.clone(), rate);
+        env.events().publish(
+            (Symbol::new(&env, "allow_feed"), reporter),
+            (kind, feed),
+        );
+    }
+
+    /// Revoke a reporter's permission to write a feed (admin only)
+    pub fn revoke_feed(env: Env, reporter: Address, kind: FeedKind, feed: Symbol) {
+        require_admin(&env);
 
-        env.storage()
-            .persistent()
-            .set(&Symbol::new(&env, "fx"), &fx_data);
+        let mut feeds = read_registered_reporter(&env, &reporter);
+        if let Some(index) = feeds.first_index_of((kind, feed.clone())) {
+            feeds.remove(index);
+            storage::write_reporter_feeds(&env, &reporter, &feeds);
+        }
 
         env.events().publish(
-            (Symbol::new(&env, "set_fx"), pair),
-            rate,
+            (Symbol::new(&env, "revoke_feed"), reporter),
+            (kind, feed),
         );
     }
 
-    /// Get FX rate for a pair
-    pub fn get_fx(env: Env, pair: Symbol) -> i128 {
-        let fx_data: Map<Symbol, i128> = env
-            .storage()
-            .persistent()
-            .get(&Symbol::new(&env, "fx"))
-            .unwrap_or(Map::new(&env));
+    /// Check whether an address is a registered reporter
+    pub fn is_reporter(env: Env, reporter: Address) -> bool {
+        storage::read_reporter_feeds(&env, &reporter).is_some()
+    }
 
-        fx_data.get(pair).unwrap_or(0)
+    /// Get the feeds a reporter is allowed to write
+    pub fn get_reporter_feeds(env: Env, reporter: Address) -> Vec<(FeedKind, Symbol)> {
+        storage::read_reporter_feeds(&env, &reporter).unwrap_or(Vec::new(&env))
     }
 
-    /// Set asset price
-    pub fn set_asset_price(env: Env, asset: Symbol, price: i128) {
-        let mut asset_data: Map<Symbol, i128> = env
-            .storage()
-            .persistent()
-            .get(&Symbol::new(&env, "assets"))
-            .unwrap_or(Map::new(&env));
+    /// Configure reporter staking (admin only). Once set, reporters need `min_stake` to submit,
+    /// and submissions further than `outlier_bps` from the round median lose `outlier_slash`.
+    /// Unstaked tokens stay slashable for `unbonding_period` seconds, which should outlast a round.
+    pub fn set_stake_config(
+        env: Env,
+        token: Address,
+        min_stake: i128,
+        outlier_bps: u32,
+        outlier_slash: i128,
+        unbonding_period: u64,
+    ) {
+        require_admin(&env);
 
-        asset_data.set(asset.clone(), price);
+        if min_stake < 0 || outlier_slash < 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        if unbonding_period == 0 {
+            panic_with_error!(&env, Error::InvalidWindow);
+        }
 
-        env.storage()
-            .persistent()
-            .set(&Symbol::new(&env, "assets"), &asset_data);
+        let config = StakeConfig {
+            token,
+            min_stake,
+            outlier_bps,
+            outlier_slash,
+            unbonding_period,
+        };
+        storage::write_stake_config(&env, &config);
 
         env.events().publish(
-            (Symbol::new(&env, "set_asset"), asset),
-            price,
+            (Symbol::new(&env, "set_stake_config"),),
+            config,
         );
     }
 
-    /// Get asset price
-    pub fn get_asset_price(env: Env, asset: Symbol) -> i128 {
-        let asset_data: Map<Symbol, i128> = env
-            .storage()
-            .persistent()
-            .get(&Symbol::new(&env, "assets"))
-            .unwrap_or(Map::new(&env));
+    /// Get the settings of reporter staking
+    pub fn get_stake_config(env: Env) -> Result<StakeConfig, Error> {
+        storage::read_stake_config(&env).ok_or(Error::StakeNotConfigured)
+    }
+
+    /// Add to a reporter's stake
+    pub fn stake(env: Env, reporter: Address, amount: i128) {
+        reporter.require_auth();
+
+        let config = read_stake_config(&env);
+        read_registered_reporter(&env, &reporter);
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
 
-        asset_data.get(asset).unwrap_or(0)
+        token::Client::new(&env, &config.token).transfer(
+            &reporter,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        let staked = storage::read_stake(&env, &reporter) + amount;
+        storage::write_stake(&env, &reporter, staked);
+
+        env.events().publish(
+            (Symbol::new(&env, "stake"), reporter),
+            staked,
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use soroban_sdk::testutils::Env as _;
+    /// Start withdrawing part of a reporter's stake. Dropping below the minimum suspends submission
+    /// rights, and the tokens stay slashable until `withdraw_unbonded` after the unbonding period.
+    /// Unstaking again restarts the period for everything still unbonding.
+    pub fn unstake(env: Env, reporter: Address, amount: i128) {
+        reporter.require_auth();
 
-    #[test]
-    fn test_set_get_cpi() {
-        let env = Env::default();
-        let contract_id = env.register_contract(None, OracleContract);
-        let client = OracleContractClient::new(&env, &contract_id);
+        let config = read_stake_config(&env);
+        let staked = storage::read_stake(&env, &reporter);
+        if amount <= 0 || amount > staked {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
 
-        let country = Symbol::new(&env, "USA");
-        client.set_cpi(&country, &250);
+        let mut unbonding = storage::read_unbonding(&env, &reporter);
+        unbonding.amount += amount;
+        unbonding.available_at = env.ledger().timestamp() + config.unbonding_period;
+        storage::write_stake(&env, &reporter, staked - amount);
+        storage::write_unbonding(&env, &reporter, &unbonding);
 
-        assert_eq!(client.get_cpi(&country), 250);
+        env.events().publish(
+            (Symbol::new(&env, "unstake"), reporter),
+            unbonding,
+        );
     }
 
-    #[test]
-    fn test_set_get_fx() {
-        let env = Env::default();
-        let contract_id = env.register_contract(None, OracleContract);
-        let client = OracleContractClient::new(&env, &contract_id);
+    /// Withdraw a reporter's unbonded stake, returning the amount paid out.
+    /// Fails with InvalidAmount while nothing has finished unbonding.
+    pub fn withdraw_unbonded(env: Env, reporter: Address) -> i128 {
+        reporter.require_auth();
 
-        let pair = Symbol::new(&env, "USDNGN");
-        client.set_fx(&pair, &1000);
+        let config = read_stake_config(&env);
+        let unbonding = storage::read_unbonding(&env, &reporter);
+        if unbonding.amount == 0 || env.ledger().timestamp() < unbonding.available_at {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
 
-        assert_eq!(client.get_fx(&pair), 1000);
+        storage::remove_unbonding(&env, &reporter);
+        token::Client::new(&env, &config.token).transfer(
+            &env.current_contract_address(),
+            &reporter,
+            &unbonding.amount,
+        );
+
+        env.events().publish(
+            (Symbol::new(&env, "withdraw_unbonded"), reporter),
+            unbonding.amount,
+        );
+
+        unbonding.amount
     }
 
-    #[test]
-    fn test_set_get_asset_price() {
-        let env = Env::default();
-        let contract_id = env.register_contract(None, OracleContract);
-        let client = OracleContractClient::new(&env, &contract_id);
+    /// Get a reporter's stake that is still unbonding
+    pub fn get_unbonding(env: Env, reporter: Address) -> Unbonding {
+        storage::read_unbonding(&env, &reporter)
+    }
 
-        let asset = Symbol::new(&env, "GOLD");
-        client.set_asset_price(&asset, &2000);
+    /// Get a reporter's stake
+    pub fn get_stake(env: Env, reporter: Address) -> i128 {
+        storage::read_stake(&env, &reporter)
+    }
 
-        assert_eq!(client.get_asset_price(&asset), 2000);
+    /// Slash a reporter's stake for bad or missed reports, sending it to the admin (admin only).
+    /// Returns the amount slashed, capped at the stake.
+    pub fn slash(env: Env, reporter: Address, amount: i128) -> i128 {
+        require_admin(&env);
+
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+
+        slash_stake(&env, &read_stake_config(&env), &reporter, amount)
+    }
+
+    /// Subscribe a consumer contract to a feed, replacing its existing trigger for the feed.
+    /// The oracle calls `on_oracle_update(feed, value)` on the consumer when the trigger fires.
+    /// Callbacks run within the reporter's transaction, so only the admin may add subscribers.
+    pub fn subscribe(env: Env, consumer: Address, kind: FeedKind, feed: Symbol, trigger: Trigger) {
+        require_admin(&env);
+
+        let mut subscriptions = storage::read_subscriptions(&env, kind, &feed);
+        let subscription = Subscription {
+            consumer: consumer.clone(),
+            trigger: trigger.clone(),
+            last_notified: 0,
+        };
+
+        match subscription_index(&subscriptions, &consumer) {
+            Some(index) => subscriptions.set(index, subscription),
+            None if subscriptions.len() >= MAX_SUBSCRIBERS => {
+                panic_with_error!(&env, Error::TooManySubscribers)
+            }
+            None => subscriptions.push_back(subscription),
+        }
+        storage::write_subscriptions(&env, kind, &feed, &subscriptions);
+
+        env.events().publish(
+            (Symbol::new(&env, "subscribe"), kind, feed),
+            (consumer, trigger),
+        );
+    }
+
+    /// Remove a consumer's subscription to a feed (admin only)
+    pub fn unsubscribe(env: Env, consumer: Address, kind: FeedKind, feed: Symbol) {
+        require_admin(&env);
+
+        let mut subscriptions = storage::read_subscriptions(&env, kind, &feed);
+        let index = subscription_index(&subscriptions, &consumer)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::SubscriptionNotFound));
+        subscriptions.remove(index);
+        storage::write_subscriptions(&env, kind, &feed, &subscriptions);
+
+        env.events().publish(
+            (Symbol::new(&env, "unsubscribe"), kind, feed),
+            consumer,
+        );
+    }
+
+    /// Get the subscriptions to a feed. Premium feeds fail with `Unauthorized`.
+    pub fn get_subscriptions(env: Env, kind: FeedKind, feed: Symbol) -> Result<Vec<Subscription>, Error> {
+        require_free(&env, kind, &feed)?;
+        Ok(storage::read_subscriptions(&env, kind, &feed))
+    }
+
+    /// Check whether a reporter may write a feed
+    pub fn can_report(env: Env, reporter: Address, kind: FeedKind, feed: Symbol) -> bool {
+        can_report(&env, &reporter, kind, &feed)
+    }
+
+    /// Register the ed25519 key a reporter signs off-chain reports with (admin only).
+    /// Replaces any key previously registered for the reporter.
+    pub fn set_reporter_key(env: Env, reporter: Address, public_key: BytesN<32>) {
+        require_admin(&env);
+
+        read_registered_reporter(&env, &reporter);
+        storage::write_reporter_key(&env, &reporter, &public_key);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_reporter_key"), reporter),
+            public_key,
+        );
+    }
+
+    /// Get the reporter a signing key is registered to
+    pub fn get_key_reporter(env: Env, public_key: BytesN<32>) -> Result<Address, Error> {
+        storage::read_key_reporter(&env, &public_key).ok_or(Error::UnknownKey)
+    }
+
+    /// Get the last nonce accepted for a signing key
+    pub fn get_nonce(env: Env, public_key: BytesN<32>) -> u64 {
+        storage::read_nonce(&env, &public_key)
+    }
+
+    /// Submit a report signed off-chain by a registered reporter key. Anyone may relay it.
+    ///
+    /// The signature covers the XDR of `(oracle address, kind, feed, value, timestamp, nonce)`.
+    /// Nonces must increase per key, and the report must be at most five minutes old.
+    /// The published value is dated by the oldest report timestamp in its round.
+    #[allow(clippy::too_many_arguments)]
+    pub fn submit_signed(
+        env: Env,
+        public_key: BytesN<32>,
+        kind: FeedKind,
+        feed: Symbol,
+        value: i128,
+        timestamp: u64,
+        nonce: u64,
+        signature: BytesN<64>,
+    ) {
+        let reporter = storage::read_key_reporter(&env, &public_key)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::UnknownKey));
+
+        let now = env.ledger().timestamp();
+        if timestamp > now || now - timestamp > SIGNED_REPORT_MAX_AGE {
+            panic_with_error!(&env, Error::InvalidTimestamp);
+        }
+
+        if nonce <= storage::read_nonce(&env, &public_key) {
+            panic_with_error!(&env, Error::InvalidNonce);
+        }
+
+        let message = (
+            env.current_contract_address(),
+            kind,
+            feed.clone(),
+            value,
+            timestamp,
+            nonce,
+        )
+            .to_xdr(&env);
+        env.crypto().ed25519_verify(&public_key, &message, &signature);
+
+        storage::write_nonce(&env, &public_key, nonce);
+
+        record_submission(&env, &reporter, kind, &feed, value, 0, timestamp);
+    }
+
+    /// Submit a value with the reporter's own uncertainty, in the feed's units.
+    /// The published confidence is the wider of the reporters' median uncertainty and their dispersion.
+    pub fn submit_with_confidence(
+        env: Env,
+        reporter: Address,
+        kind: FeedKind,
+        feed: Symbol,
+        value: i128,
+        confidence: i128,
+    ) {
+        reporter.require_auth();
+
+        if confidence < 0 {
+            panic_with_error!(&env, Error::InvalidValue);
+        }
+
+        record_submission(&env, &reporter, kind, &feed, value, confidence, env.ledger().timestamp());
+    }
+
+    /// Get a feed's value, confidence and the timestamp of its last update.
+    /// Confidence is a half-width in the feed's units; zero when no spread was observed.
+    pub fn get_with_confidence(env: Env, kind: FeedKind, feed: Symbol) -> Result<(i128, i128, u64), Error> {
+        let value = read_published_value(&env, kind, &feed)?;
+        Ok((value.value, storage::read_confidence(&env, kind, &feed), value.timestamp))
+    }
+
+    /// Enable commit-reveal rounds with the given phase lengths in seconds and the stake
+    /// penalty for unrevealed commits (admin only). Direct submissions are rejected while enabled.
+    /// A penalty requires reporter staking to be configured.
+    pub fn set_commit_reveal(env: Env, commit_period: u64, reveal_period: u64, penalty: i128) {
+        require_admin(&env);
+
+        if commit_period == 0 || reveal_period == 0 {
+            panic_with_error!(&env, Error::InvalidWindow);
+        }
+        if penalty < 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        if penalty > 0 && storage::read_stake_config(&env).is_none() {
+            panic_with_error!(&env, Error::StakeNotConfigured);
+        }
+
+        let config = CommitRevealConfig {
+            commit_period,
+            reveal_period,
+            penalty,
+        };
+        storage::write_commit_reveal_config(&env, &config);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_commit_reveal"),),
+            config,
+        );
+    }
+
+    /// Disable commit-reveal rounds and accept direct submissions again (admin only)
+    pub fn disable_commit_reveal(env: Env) {
+        require_admin(&env);
+
+        storage::remove_commit_reveal_config(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "disable_commit_reveal"),),
+            (),
+        );
+    }
+
+    /// Get the commit-reveal round settings, if enabled
+    pub fn get_commit_reveal(env: Env) -> Option<CommitRevealConfig> {
+        storage::read_commit_reveal_config(&env)
+    }
+
+    /// Commit the SHA-256 of the XDR of `(reporter, kind, feed, round id, value, salt)` for a feed.
+    /// The round id is the feed's `get_round` id. The first commit opens the round.
+    pub fn commit(env: Env, reporter: Address, kind: FeedKind, feed: Symbol, commitment: BytesN<32>) {
+        require_reporter(&env, &reporter, kind, &feed);
+        require_not_basket(&env, kind, &feed);
+        require_accepts_value(&env, kind, &feed);
+        require_stake(&env, &reporter);
+
+        let config = storage::read_commit_reveal_config(&env)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NoCommitRound));
+        let now = env.ledger().timestamp();
+
+        let mut round = match storage::read_commit_round(&env, kind, &feed) {
+            Some(round) if now >= round.commit_end => panic_with_error!(&env, Error::CommitPhaseClosed),
+            Some(round) => round,
+            None => CommitRound {
+                id: storage::read_round(&env, kind, &feed).id,
+                commit_end: now + config.commit_period,
+                reveal_end: now + config.commit_period + config.reveal_period,
+                commits: Map::new(&env),
+                reveals: Map::new(&env),
+            },
+        };
+
+        if round.commits.contains_key(reporter.clone()) {
+            panic_with_error!(&env, Error::AlreadySubmitted);
+        }
+
+        round.commits.set(reporter.clone(), commitment);
+        storage::write_commit_round(&env, kind, &feed, &round);
+
+        env.events().publish(
+            (Symbol::new(&env, "commit"), kind, feed),
+            (reporter, round.id),
+        );
+    }
+
+    /// Reveal a committed value and its salt during the reveal phase
+    pub fn reveal(env: Env, reporter: Address, kind: FeedKind, feed: Symbol, value: i128, salt: BytesN<32>) {
+        reporter.require_auth();
+
+        let mut round = storage::read_commit_round(&env, kind, &feed)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NoCommitRound));
+        let now = env.ledger().timestamp();
+        if now < round.commit_end || now >= round.reveal_end {
+            panic_with_error!(&env, Error::NotRevealPhase);
+        }
+
+        let commitment = round
+            .commits
+            .get(reporter.clone())
+            .unwrap_or_else(|| panic_with_error!(&env, Error::InvalidReveal));
+        let hash = env
+            .crypto()
+            .sha256(&(reporter.clone(), kind, feed.clone(), round.id, value, salt).to_xdr(&env));
+        if hash.to_array() != commitment.to_array() {
+            panic_with_error!(&env, Error::InvalidReveal);
+        }
+        if round.reveals.contains_key(reporter.clone()) {
+            panic_with_error!(&env, Error::AlreadySubmitted);
+        }
+        require_valid_value(&env, kind, &feed, value);
+
+        round.reveals.set(reporter.clone(), value);
+        storage::write_commit_round(&env, kind, &feed, &round);
+
+        env.events().publish(
+            (Symbol::new(&env, "reveal"), kind, feed),
+            (reporter, round.id, value),
+        );
+    }
+
+    /// Close a commit-reveal round after its reveal phase. Unrevealed commits lose the configured
+    /// penalty from their stake, and the median of the reveals is published if they reach quorum.
+    /// A round that reaches quorum waits while the feed is paused, retired or tripped.
+    pub fn finalize_round(env: Env, kind: FeedKind, feed: Symbol) {
+        let round = storage::read_commit_round(&env, kind, &feed)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NoCommitRound));
+        if env.ledger().timestamp() < round.reveal_end {
+            panic_with_error!(&env, Error::RevealPhaseOpen);
+        }
+
+        storage::remove_commit_round(&env, kind, &feed);
+
+        let penalty = storage::read_commit_reveal_config(&env).map_or(0, |config| config.penalty);
+        let stake_config = storage::read_stake_config(&env);
+        for reporter in round.commits.keys().iter() {
+            if round.reveals.contains_key(reporter.clone()) {
+                continue;
+            }
+
+            if let Some(stake_config) = &stake_config {
+                if penalty > 0 {
+                    slash_stake(&env, stake_config, &reporter, penalty);
+                }
+            }
+
+            env.events().publish(
+                (Symbol::new(&env, "unrevealed"), kind, feed.clone()),
+                (reporter, round.id),
+            );
+        }
+
+        // Reveals of reporters removed or revoked since committing do not count
+        let mut reveals = Round {
+            id: round.id,
+            submissions: round.reveals,
+            opened_at: round.commit_end,
+            observed_at: env.ledger().timestamp(),
+        };
+        drop_ineligible(&env, kind, &feed, &mut reveals, &mut Map::new(&env));
+
+        if reveals.submissions.len() < storage::read_quorum(&env) {
+            env.events().publish(
+                (Symbol::new(&env, "round_failed"), kind, feed.clone()),
+                (round.id, reveals.submissions.len()),
+            );
+            return;
+        }
+
+        // The round waits while the feed is paused or its circuit breaker holds a value
+        require_accepts_value(&env, kind, &feed);
+        close_round(&env, kind, &feed, &reveals, &Map::new(&env));
+        storage::write_round(&env, kind, &feed, &Round::new(&env, round.id + 1));
+    }
+
+    /// Get the open commit-reveal round of a feed
+    pub fn get_commit_round(env: Env, kind: FeedKind, feed: Symbol) -> Result<CommitRound, Error> {
+        require_free(&env, kind, &feed)?;
+        storage::read_commit_round(&env, kind, &feed).ok_or(Error::NoCommitRound)
+    }
+
+    /// Set the number of reporter submissions needed to close a round (admin only)
+    pub fn set_quorum(env: Env, quorum: u32) {
+        require_admin(&env);
+
+        if quorum == 0 {
+            panic_with_error!(&env, Error::InvalidQuorum);
+        }
+
+        storage::write_quorum(&env, quorum);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_quorum"),),
+            quorum,
+        );
+    }
+
+    /// Get the number of reporter submissions needed to close a round
+    pub fn get_quorum(env: Env) -> u32 {
+        storage::read_quorum(&env)
+    }
+
+    /// Set the seconds a round collects submissions before its submissions are dropped (admin only)
+    pub fn set_round_max_age(env: Env, max_age: u64) {
+        require_admin(&env);
+
+        if max_age == 0 {
+            panic_with_error!(&env, Error::InvalidWindow);
+        }
+
+        storage::write_round_max_age(&env, max_age);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_round_max_age"),),
+            max_age,
+        );
+    }
+
+    /// Get the seconds a round collects submissions before it expires
+    pub fn get_round_max_age(env: Env) -> u64 {
+        storage::read_round_max_age(&env)
+    }
+
+    /// Register or update the metadata of a feed (admin only).
+    /// Values submitted to a registered feed must be positive and fit its declared decimals.
+    pub fn register_feed(env: Env, kind: FeedKind, feed: Symbol, metadata: FeedMetadata) {
+        require_admin(&env);
+
+        if metadata.decimals > MAX_DECIMALS {
+            panic_with_error!(&env, Error::InvalidDecimals);
+        }
+
+        storage::write_metadata(&env, kind, &feed, &metadata);
+
+        env.events().publish(
+            (Symbol::new(&env, "register_feed"), kind, feed),
+            metadata,
+        );
+    }
+
+    /// Get the metadata of a feed
+    pub fn get_feed_metadata(env: Env, kind: FeedKind, feed: Symbol) -> Result<FeedMetadata, Error> {
+        storage::read_metadata(&env, kind, &feed).ok_or(Error::FeedNotRegistered)
+    }
+
+    /// Pause a feed, isolating it from submissions and consumers until resumed (admin only)
+    pub fn pause_feed(env: Env, kind: FeedKind, feed: Symbol) {
+        set_feed_status(&env, kind, &feed, FeedStatus::Paused);
+    }
+
+    /// Resume a paused or deprecated feed, recomputing it if it is a basket (admin only)
+    pub fn resume_feed(env: Env, kind: FeedKind, feed: Symbol) {
+        set_feed_status(&env, kind, &feed, FeedStatus::Active);
+        storage::remove_successor(&env, kind, &feed);
+
+        if kind == FeedKind::Cpi {
+            recompute_basket(&env, &feed);
+        }
+    }
+
+    /// Deprecate a feed in favour of a successor feed of the same kind (admin only)
+    pub fn deprecate_feed(env: Env, kind: FeedKind, feed: Symbol, successor: Symbol) {
+        set_feed_status(&env, kind, &feed, FeedStatus::Deprecated);
+        storage::write_successor(&env, kind, &feed, &successor);
+
+        env.events().publish(
+            (Symbol::new(&env, "deprecate_feed"), kind, feed),
+            successor,
+        );
+    }
+
+    /// Retire a feed permanently, keeping any successor it was deprecated in favour of (admin only)
+    pub fn retire_feed(env: Env, kind: FeedKind, feed: Symbol) {
+        set_feed_status(&env, kind, &feed, FeedStatus::Retired);
+    }
+
+    /// Get the lifecycle state of a feed
+    pub fn get_feed_status(env: Env, kind: FeedKind, feed: Symbol) -> FeedStatus {
+        storage::read_feed_status(&env, kind, &feed)
+    }
+
+    /// Get the feed that replaces a deprecated or retired feed
+    pub fn get_successor(env: Env, kind: FeedKind, feed: Symbol) -> Option<Symbol> {
+        storage::read_successor(&env, kind, &feed)
+    }
+
+    /// Set the token premium read fees are paid in and the treasury they are withdrawn to (admin only)
+    pub fn set_fee_config(env: Env, token: Address, treasury: Address) {
+        require_admin(&env);
+
+        let config = FeeConfig { token, treasury };
+        storage::write_fee_config(&env, &config);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_fee_config"),),
+            config,
+        );
+    }
+
+    /// Get the read fee token and treasury
+    pub fn get_fee_config(env: Env) -> Result<FeeConfig, Error> {
+        storage::read_fee_config(&env).ok_or(Error::NotInitialized)
+    }
+
+    /// Make a feed premium by charging `fee` per read through `read_premium`; zero makes it free (admin only).
+    /// Free getters fail with `Unauthorized` for premium feeds.
+    pub fn set_read_fee(env: Env, kind: FeedKind, feed: Symbol, fee: i128) {
+        require_admin(&env);
+
+        if fee < 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        if fee > 0 && storage::read_fee_config(&env).is_none() {
+            panic_with_error!(&env, Error::NotInitialized);
+        }
+
+        storage::write_read_fee(&env, kind, &feed, fee);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_read_fee"), kind, feed),
+            fee,
+        );
+    }
+
+    /// Get the per-read fee of a feed; zero for free feeds
+    pub fn get_read_fee(env: Env, kind: FeedKind, feed: Symbol) -> i128 {
+        storage::read_read_fee(&env, kind, &feed)
+    }
+
+    /// Let a consumer, such as another Aureus contract, read premium feeds for free through
+    /// `read_premium` and receive their subscription callbacks (admin only)
+    pub fn set_free_reader(env: Env, consumer: Address, free: bool) {
+        require_admin(&env);
+
+        storage::write_free_reader(&env, &consumer, free);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_free_reader"), consumer),
+            free,
+        );
+    }
+
+    /// Check whether a consumer reads premium feeds for free
+    pub fn is_free_reader(env: Env, consumer: Address) -> bool {
+        storage::read_free_reader(&env, &consumer)
+    }
+
+    /// Prepay read fees for a consumer
+    pub fn deposit_credit(env: Env, payer: Address, consumer: Address, amount: i128) {
+        payer.require_auth();
+
+        let config = read_fee_config(&env);
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+
+        token::Client::new(&env, &config.token).transfer(&payer, &env.current_contract_address(), &amount);
+
+        let credit = storage::read_credit(&env, &consumer) + amount;
+        storage::write_credit(&env, &consumer, credit);
+
+        env.events().publish(
+            (Symbol::new(&env, "deposit_credit"), consumer),
+            credit,
+        );
+    }
+
+    /// Get a consumer's prepaid read fee balance
+    pub fn get_credit(env: Env, consumer: Address) -> i128 {
+        storage::read_credit(&env, &consumer)
+    }
+
+    /// Read any feed as a consumer. Premium reads are free for whitelisted consumers, else
+    /// charged to the consumer's prepaid credit, or transferred from the consumer when it runs short.
+    pub fn read_premium(env: Env, consumer: Address, kind: FeedKind, feed: Symbol) -> Result<FeedValue, Error> {
+        consumer.require_auth();
+
+        require_active(&env, kind, &feed)?;
+        let value = storage::read_value(&env, kind, &feed).ok_or(Error::FeedNotFound)?;
+
+        let fee = storage::read_read_fee(&env, kind, &feed);
+        if fee > 0 && !storage::read_free_reader(&env, &consumer) {
+            charge_read_fee(&env, &consumer, fee);
+
+            env.events().publish(
+                (Symbol::new(&env, "read_fee"), kind, feed),
+                (consumer, fee),
+            );
+        }
+
+        Ok(value)
+    }
+
+    /// Get the collected read fees not yet withdrawn
+    pub fn get_fee_balance(env: Env) -> i128 {
+        storage::read_fee_balance(&env)
+    }
+
+    /// Withdraw collected read fees to the treasury (admin only)
+    pub fn withdraw_fees(env: Env, amount: i128) {
+        require_admin(&env);
+
+        let config = read_fee_config(&env);
+        let balance = storage::read_fee_balance(&env);
+        if amount <= 0 || amount > balance {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+
+        storage::write_fee_balance(&env, balance - amount);
+        token::Client::new(&env, &config.token).transfer(
+            &env.current_contract_address(),
+            &config.treasury,
+            &amount,
+        );
+
+        env.events().publish(
+            (Symbol::new(&env, "withdraw_fees"), config.treasury),
+            amount,
+        );
+    }
+
+    /// Health of up to `limit` published or registered feeds starting at `offset`, for spotting
+    /// overdue feeds page by page. Heartbeats come from the feed's registered metadata.
+    pub fn feed_health(env: Env, offset: u32, limit: u32) -> Vec<FeedHealth> {
+        let now = env.ledger().timestamp();
+
+        let published = storage::read_feeds(&env);
+        let mut feeds = published.clone();
+        for registered in storage::read_registered_feeds(&env).iter() {
+            if !published.contains(registered.clone()) {
+                feeds.push_back(registered);
+            }
+        }
+
+        let start = offset.min(feeds.len());
+        let end = offset.saturating_add(limit).min(feeds.len());
+
+        let mut health = Vec::new(&env);
+        for (kind, feed) in feeds.slice(start..end).iter() {
+            let value = storage::read_value(&env, kind, &feed);
+            let age = value.as_ref().map_or(u64::MAX, |value| now.saturating_sub(value.timestamp));
+            let heartbeat = storage::read_metadata(&env, kind, &feed).map_or(0, |metadata| metadata.heartbeat);
+
+            health.push_back(FeedHealth {
+                kind,
+                feed: feed.clone(),
+                never_updated: value.is_none(),
+                age,
+                missed_heartbeats: age.checked_div(heartbeat).unwrap_or(0),
+                last_participants: storage::read_participants(&env, kind, &feed),
+                open_submissions: storage::read_round(&env, kind, &feed).submissions.len(),
+                tripped: storage::read_pending(&env, kind, &feed).is_some(),
+                status: storage::read_feed_status(&env, kind, &feed),
+            });
+        }
+
+        health
+    }
+
+    /// Set the largest change from the previous value, in basis points, that a feed
+    /// publishes without admin confirmation (admin only). Zero disables the circuit breaker.
+    pub fn set_max_deviation(env: Env, kind: FeedKind, feed: Symbol, max_bps: u32) {
+        require_admin(&env);
+
+        storage::write_max_deviation(&env, kind, &feed, max_bps);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_max_deviation"), kind, feed),
+            max_bps,
+        );
+    }
+
+    /// Get the circuit breaker threshold of a feed in basis points; zero when disabled
+    pub fn get_max_deviation(env: Env, kind: FeedKind, feed: Symbol) -> u32 {
+        storage::read_max_deviation(&env, kind, &feed)
+    }
+
+    /// Check whether a feed's circuit breaker has tripped
+    pub fn is_tripped(env: Env, kind: FeedKind, feed: Symbol) -> bool {
+        storage::read_pending(&env, kind, &feed).is_some()
+    }
+
+    /// Get the value held by a tripped feed's circuit breaker. Premium feeds fail with `Unauthorized`.
+    pub fn get_pending(env: Env, kind: FeedKind, feed: Symbol) -> Result<FeedValue, Error> {
+        require_free(&env, kind, &feed)?;
+        storage::read_pending(&env, kind, &feed).ok_or(Error::NoPendingValue)
+    }
+
+    /// Publish the value held by a tripped feed and reset its circuit breaker (admin only).
+    /// A paused feed keeps its pending value, and a retired feed's is rejected instead.
+    pub fn confirm_pending(env: Env, kind: FeedKind, feed: Symbol) {
+        require_admin(&env);
+
+        if is_retired(&env, kind, &feed) {
+            return discard_pending(&env, kind, &feed);
+        }
+        require_writable(&env, kind, &feed);
+
+        let pending = take_pending(&env, kind, &feed);
+        let confidence = storage::read_pending_confidence(&env, kind, &feed);
+        storage::remove_pending_confidence(&env, kind, &feed);
+        publish_value(&env, kind, &feed, pending.value, confidence, pending.timestamp);
+
+        env.events().publish(
+            (Symbol::new(&env, "confirm_pending"), kind, feed),
+            pending.value,
+        );
+    }
+
+    /// Discard the value held by a tripped feed and reset its circuit breaker (admin only)
+    pub fn reject_pending(env: Env, kind: FeedKind, feed: Symbol) {
+        require_admin(&env);
+        discard_pending(&env, kind, &feed);
+    }
+
+    /// Get the open round for a feed. Premium feeds fail with `Unauthorized`.
+    pub fn get_round(env: Env, kind: FeedKind, feed: Symbol) -> Result<Round, Error> {
+        require_free(&env, kind, &feed)?;
+        Ok(storage::read_round(&env, kind, &feed))
+    }
+
+    /// Set how many price observations are kept per FX and asset feed (admin only)
+    pub fn set_observation_limit(env: Env, limit: u32) {
+        require_admin(&env);
+
+        if limit < 2 {
+            panic_with_error!(&env, Error::InvalidHistoryLimit);
+        }
+
+        storage::write_observation_limit(&env, limit);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_observation_limit"),),
+            limit,
+        );
+    }
+
+    /// Get how many price observations are kept per FX and asset feed
+    pub fn get_observation_limit(env: Env) -> u32 {
+        storage::read_observation_limit(&env)
+    }
+
+    /// Get the buffered price observations of an FX or asset feed, oldest first
+    pub fn get_observations(env: Env, kind: FeedKind, feed: Symbol) -> Result<Vec<Observation>, Error> {
+        require_readable(&env, kind, &feed)?;
+        Ok(storage::read_observations(&env, kind, &feed).unwrap_or(Vec::new(&env)))
+    }
+
+    /// Submit values for several CPI, FX and asset feeds in one call
+    pub fn set_many(env: Env, reporter: Address, updates: Vec<(FeedKind, Symbol, i128)>) {
+        reporter.require_auth();
+
+        for (kind, feed, value) in updates.iter() {
+            record_submission(&env, &reporter, kind, &feed, value, 0, env.ledger().timestamp());
+        }
+    }
+
+    /// Get the published values of several feeds, `None` where a feed has no value
+    pub fn get_many(env: Env, feeds: Vec<(FeedKind, Symbol)>) -> Vec<Option<FeedValue>> {
+        let mut values = Vec::new(&env);
+        for (kind, feed) in feeds.iter() {
+            values.push_back(read_published_value(&env, kind, &feed).ok());
+        }
+
+        values
+    }
+
+    /// Configure optimistic CPI proposals: bond token and size, dispute window in seconds and arbiter (admin only)
+    pub fn set_optimistic_config(env: Env, token: Address, bond: i128, dispute_window: u64, arbiter: Address) {
+        require_admin(&env);
+
+        if bond <= 0 {
+            panic_with_error!(&env, Error::InvalidBond);
+        }
+        if dispute_window == 0 {
+            panic_with_error!(&env, Error::InvalidWindow);
+        }
+
+        let config = OptimisticConfig {
+            token,
+            bond,
+            dispute_window,
+            arbiter,
+        };
+        storage::write_optimistic_config(&env, &config);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_optimistic_config"),),
+            config,
+        );
+    }
+
+    /// Get the settings of optimistic CPI proposals
+    pub fn get_optimistic_config(env: Env) -> Result<OptimisticConfig, Error> {
+        storage::read_optimistic_config(&env).ok_or(Error::OptimisticNotConfigured)
+    }
+
+    /// Propose a CPI value for a country, posting the configured bond.
+    /// Anyone may propose; the value is published by `settle_cpi` unless disputed.
+    pub fn propose_cpi(env: Env, proposer: Address, country: Symbol, cpi: i128) {
+        proposer.require_auth();
+
+        let config = storage::read_optimistic_config(&env)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OptimisticNotConfigured));
+
+        if cpi <= 0 {
+            panic_with_error!(&env, Error::InvalidValue);
+        }
+        require_not_basket(&env, FeedKind::Cpi, &country);
+        require_writable(&env, FeedKind::Cpi, &country);
+        require_valid_value(&env, FeedKind::Cpi, &country, cpi);
+
+        if storage::read_proposal(&env, &country).is_some() {
+            panic_with_error!(&env, Error::ProposalExists);
+        }
+
+        token::Client::new(&env, &config.token).transfer(
+            &proposer,
+            &env.current_contract_address(),
+            &config.bond,
+        );
+
+        storage::write_proposal(
+            &env,
+            &country,
+            &Proposal {
+                proposer: proposer.clone(),
+                value: cpi,
+                bond: config.bond,
+                token: config.token,
+                proposed_at: env.ledger().timestamp(),
+                dispute_window: config.dispute_window,
+                disputed: false,
+            },
+        );
+
+        env.events().publish(
+            (Symbol::new(&env, "propose_cpi"), country),
+            (proposer, cpi),
+        );
+    }
+
+    /// Dispute an open CPI proposal within its dispute window, posting a bond matching the proposer's
+    pub fn dispute_cpi(env: Env, challenger: Address, country: Symbol) {
+        challenger.require_auth();
+
+        let mut proposal = read_proposal(&env, &country);
+        if proposal.disputed {
+            panic_with_error!(&env, Error::ProposalDisputed);
+        }
+        if env.ledger().timestamp() >= proposal.proposed_at + proposal.dispute_window {
+            panic_with_error!(&env, Error::DisputeWindowClosed);
+        }
+
+        token::Client::new(&env, &proposal.token).transfer(
+            &challenger,
+            &env.current_contract_address(),
+            &proposal.bond,
+        );
+
+        proposal.disputed = true;
+        storage::write_proposal(&env, &country, &proposal);
+        storage::write_challenger(&env, &country, &challenger);
+
+        env.events().publish(
+            (Symbol::new(&env, "dispute_cpi"), country),
+            challenger,
+        );
+    }
+
+    /// Publish an undisputed CPI proposal once its dispute window has passed and return the proposer's bond.
+    /// The value goes through the circuit breaker like a closed round; a retired feed only refunds the bond.
+    pub fn settle_cpi(env: Env, country: Symbol) {
+        let proposal = read_proposal(&env, &country);
+        if proposal.disputed {
+            panic_with_error!(&env, Error::ProposalDisputed);
+        }
+        if env.ledger().timestamp() < proposal.proposed_at + proposal.dispute_window {
+            panic_with_error!(&env, Error::DisputeWindowOpen);
+        }
+
+        let retired = is_retired(&env, FeedKind::Cpi, &country);
+        if !retired {
+            require_accepts_value(&env, FeedKind::Cpi, &country);
+        }
+
+        storage::remove_proposal(&env, &country);
+        if !retired {
+            publish_or_hold(&env, FeedKind::Cpi, &country, proposal.value, 0, env.ledger().timestamp());
+        }
+
+        token::Client::new(&env, &proposal.token).transfer(
+            &env.current_contract_address(),
+            &proposal.proposer,
+            &proposal.bond,
+        );
+
+        env.events().publish(
+            (Symbol::new(&env, "settle_cpi"), country),
+            proposal.value,
+        );
+    }
+
+    /// Resolve a disputed CPI proposal (arbiter only). The winner receives both bonds,
+    /// and the proposed value is published if the proposer wins and the feed is not retired.
+    pub fn resolve_dispute(env: Env, country: Symbol, proposer_wins: bool) {
+        let config = storage::read_optimistic_config(&env)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::OptimisticNotConfigured));
+        config.arbiter.require_auth();
+
+        let proposal = read_proposal(&env, &country);
+        let challenger = storage::read_challenger(&env, &country)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NotDisputed));
+        let publish = proposer_wins && !is_retired(&env, FeedKind::Cpi, &country);
+        if publish {
+            require_accepts_value(&env, FeedKind::Cpi, &country);
+        }
+
+        storage::remove_proposal(&env, &country);
+        storage::remove_challenger(&env, &country);
+
+        if publish {
+            publish_or_hold(&env, FeedKind::Cpi, &country, proposal.value, 0, env.ledger().timestamp());
+        }
+        let winner = if proposer_wins {
+            proposal.proposer
+        } else {
+            challenger
+        };
+
+        token::Client::new(&env, &proposal.token).transfer(
+            &env.current_contract_address(),
+            &winner,
+            &(proposal.bond * 2),
+        );
+
+        env.events().publish(
+            (Symbol::new(&env, "resolve_dispute"), country),
+            (winner, proposer_wins),
+        );
+    }
+
+    /// Get the open CPI proposal for a country
+    pub fn get_proposal(env: Env, country: Symbol) -> Result<Proposal, Error> {
+        storage::read_proposal(&env, &country).ok_or(Error::ProposalNotFound)
+    }
+
+    /// Get the challenger of a country's disputed CPI proposal
+    pub fn get_challenger(env: Env, country: Symbol) -> Result<Address, Error> {
+        storage::read_challenger(&env, &country).ok_or(Error::NotDisputed)
+    }
+
+    /// Set the oracles consulted, in order, when a feed is missing or stale (admin only)
+    pub fn set_fallbacks(env: Env, fallbacks: Vec<Fallback>) {
+        require_admin(&env);
+
+        if fallbacks.len() > MAX_FALLBACKS {
+            panic_with_error!(&env, Error::TooManyFallbacks);
+        }
+
+        storage::write_fallbacks(&env, &fallbacks);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_fallbacks"),),
+            fallbacks,
+        );
+    }
+
+    /// Get the fallback oracles in the order they are consulted
+    pub fn get_fallbacks(env: Env) -> Vec<Fallback> {
+        storage::read_fallbacks(&env)
+    }
+
+    /// Get a feed value no older than `max_age` seconds from this oracle, or else from the
+    /// first fallback oracle that has one. Fails with this oracle's error if none does.
+    /// Only missing or stale values fall back; paused, retired and premium feeds fail outright.
+    /// Fallback values are converted to the feed's registered decimals, or for unregistered
+    /// assets to the SEP-40 price decimals.
+    pub fn get_with_fallback(env: Env, kind: FeedKind, feed: Symbol, max_age: u64) -> Result<SourcedValue, Error> {
+        let error = match read_fresh_value(&env, kind, &feed, max_age) {
+            Ok(value) => {
+                let timestamp = storage::read_value(&env, kind, &feed).map_or(0, |value| value.timestamp);
+                return Ok(SourcedValue {
+                    value,
+                    timestamp,
+                    source: env.current_contract_address(),
+                });
+            }
+            Err(error @ (Error::FeedNotFound | Error::Stale)) => error,
+            Err(error) => return Err(error),
+        };
+
+        let now = env.ledger().timestamp();
+        for fallback in storage::read_fallbacks(&env).iter() {
+            if let Some(value) = read_fallback(&env, &fallback, kind, &feed) {
+                if now.saturating_sub(value.timestamp) <= max_age {
+                    return Ok(value);
+                }
+            }
+        }
+
+        Err(error)
+    }
+
+    /// Submit CPI for a country. The published value is the median of the round.
+    pub fn set_cpi(env: Env, reporter: Address, country: Symbol, cpi: i128) {
+        submit_value(&env, &reporter, FeedKind::Cpi, &country, cpi);
+    }
+
+    /// Get CPI for a country, failing with `FeedNotFound` if none was published
+    pub fn get_cpi(env: Env, country: Symbol) -> Result<i128, Error> {
+        read_published_value(&env, FeedKind::Cpi, &country).map(|cpi| cpi.value)
+    }
+
+    /// Get CPI for a country with the ledger timestamp and sequence of its last update
+    pub fn get_cpi_with_timestamp(env: Env, country: Symbol) -> Result<FeedValue, Error> {
+        read_published_value(&env, FeedKind::Cpi, &country)
+    }
+
+    /// Get CPI for a country, failing if it was not updated within `max_age` seconds
+    pub fn get_cpi_fresh(env: Env, country: Symbol, max_age: u64) -> Result<i128, Error> {
+        read_fresh_value(&env, FeedKind::Cpi, &country, max_age)
+    }
+
+    /// Define a regional CPI index, such as `WAFRICA`, as country CPI feeds weighted in basis points
+    /// summing to 10,000 (admin only). It is recomputed whenever a component publishes and is read with `get_cpi`.
+    /// Components, and the basket if registered, must declare the same decimals.
+    pub fn define_basket(env: Env, basket: Symbol, components: Vec<(Symbol, u32)>) {
+        require_admin(&env);
+
+        let mut total: u32 = 0;
+        for (country, weight) in components.iter() {
+            if weight == 0 || country == basket || storage::read_basket(&env, &country).is_some() {
+                panic_with_error!(&env, Error::InvalidValue);
+            }
+            total = total.saturating_add(weight);
+        }
+        // Baskets cannot nest, so a basket is never itself a component
+        if total as i128 != BPS || !storage::read_baskets_of(&env, &basket).is_empty() {
+            panic_with_error!(&env, Error::InvalidValue);
+        }
+        if !basket_decimals_match(&env, &basket, &components) {
+            panic_with_error!(&env, Error::InvalidDecimals);
+        }
+
+        if let Some(previous) = storage::read_basket(&env, &basket) {
+            unlink_basket(&env, &basket, &previous);
+        }
+        for (country, _) in components.iter() {
+            let mut baskets = storage::read_baskets_of(&env, &country);
+            if !baskets.contains(basket.clone()) {
+                baskets.push_back(basket.clone());
+                storage::write_baskets_of(&env, &country, &baskets);
+            }
+        }
+        storage::write_basket(&env, &basket, &components);
+
+        env.events().publish(
+            (Symbol::new(&env, "define_basket"), basket.clone()),
+            components,
+        );
+
+        recompute_basket(&env, &basket);
+    }
+
+    /// Remove a regional CPI index definition; its last value stays readable (admin only)
+    pub fn remove_basket(env: Env, basket: Symbol) {
+        require_admin(&env);
+
+        let components = storage::read_basket(&env, &basket)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::FeedNotFound));
+        unlink_basket(&env, &basket, &components);
+        storage::remove_basket(&env, &basket);
+
+        env.events().publish(
+            (Symbol::new(&env, "remove_basket"), basket),
+            (),
+        );
+    }
+
+    /// Get the weighted components of a regional CPI index
+    pub fn get_basket(env: Env, basket: Symbol) -> Result<Vec<(Symbol, u32)>, Error> {
+        storage::read_basket(&env, &basket).ok_or(Error::FeedNotFound)
+    }
+
+    /// Set how many periods of CPI history are kept per country (admin only)
+    pub fn set_history_limit(env: Env, limit: u32) {
+        require_admin(&env);
+
+        if limit == 0 {
+            panic_with_error!(&env, Error::InvalidHistoryLimit);
+        }
+
+        storage::write_history_limit(&env, limit);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_history_limit"),),
+            limit,
+        );
+    }
+
+    /// Get how many periods of CPI history are kept per country
+    pub fn get_history_limit(env: Env) -> u32 {
+        storage::read_history_limit(&env)
+    }
+
+    /// Record the CPI index level of a country for a YYYYMM period.
+    /// The feed must be writable and the reporter staked, and an index further from the previous
+    /// period's than the feed's max deviation is rejected. The oldest period is dropped once the
+    /// history limit is exceeded.
+    pub fn record_cpi(env: Env, reporter: Address, country: Symbol, period: u32, index: i128) {
+        require_reporter(&env, &reporter, FeedKind::Cpi, &country);
+        require_not_basket(&env, FeedKind::Cpi, &country);
+        require_writable(&env, FeedKind::Cpi, &country);
+        require_stake(&env, &reporter);
+
+        if !is_valid_period(period) {
+            panic_with_error!(&env, Error::InvalidPeriod);
+        }
+        if index <= 0 {
+            panic_with_error!(&env, Error::InvalidValue);
+        }
+        require_valid_value(&env, FeedKind::Cpi, &country, index);
+
+        let mut periods = storage::read_cpi_history(&env, &country);
+        let max_bps = storage::read_max_deviation(&env, FeedKind::Cpi, &country);
+        let previous = periods
+            .keys()
+            .iter()
+            .filter(|recorded| *recorded < period)
+            .last()
+            .map(|recorded| periods.get_unchecked(recorded));
+        if previous.is_some_and(|previous| deviates(max_bps, previous, index)) {
+            panic_with_error!(&env, Error::DeviationTooLarge);
+        }
+        periods.set(period, index);
+
+        while periods.len() > storage::read_history_limit(&env) {
+            let oldest = periods.keys().get_unchecked(0);
+            periods.remove(oldest);
+        }
+
+        storage::write_cpi_history(&env, &country, &periods);
+
+        env.events().publish(
+            (Symbol::new(&env, "record_cpi"), country),
+            (period, index),
+        );
+    }
+
+    /// Get the CPI index level of a country for a YYYYMM period
+    pub fn get_cpi_at(env: Env, country: Symbol, period: u32) -> Result<i128, Error> {
+        read_cpi_index(&env, &country, period)
+    }
+
+    /// Get the recorded CPI index levels of a country for periods in `[from, to]`
+    pub fn get_cpi_range(env: Env, country: Symbol, from: u32, to: u32) -> Result<Vec<(u32, i128)>, Error> {
+        require_readable(&env, FeedKind::Cpi, &country)?;
+
+        let mut range = Vec::new(&env);
+        for (period, index) in storage::read_cpi_history(&env, &country).iter() {
+            if period >= from && period <= to {
+                range.push_back((period, index));
+            }
+        }
+
+        Ok(range)
+    }
+
+    /// Get the year-over-year inflation of a country for a YYYYMM period, in basis points
+    pub fn get_yoy_inflation(env: Env, country: Symbol, period: u32) -> Result<i128, Error> {
+        if !is_valid_period(period) {
+            return Err(Error::InvalidPeriod);
+        }
+
+        let current = read_cpi_index(&env, &country, period)?;
+        let year_ago = read_cpi_index(&env, &country, period - 100)?;
+
+        mul_div(current - year_ago, BPS, year_ago)
+    }
+
+    /// Annualized inflation, in basis points, implied by CPI rising from `from_index` to `to_index` over `months`
+    pub fn annualize_inflation(from_index: i128, to_index: i128, months: u32) -> Result<i128, Error> {
+        annualized_rate(from_index, to_index, months as i128)
+    }
+
+    /// Annualized inflation of a country between two recorded YYYYMM periods, in basis points
+    pub fn get_annualized_inflation(env: Env, country: Symbol, from: u32, to: u32) -> Result<i128, Error> {
+        if !is_valid_period(from) || !is_valid_period(to) {
+            return Err(Error::InvalidPeriod);
+        }
+
+        let from_index = read_cpi_index(&env, &country, from)?;
+        let to_index = read_cpi_index(&env, &country, to)?;
+
+        annualized_rate(from_index, to_index, math::months_between(from, to))
+    }
+
+    /// CPI index level reached after `months` of inflation at an annual rate in basis points
+    pub fn project_cpi(index: i128, annual_rate_bps: i128, months: u32) -> Result<i128, Error> {
+        if index <= 0 || annual_rate_bps <= -BPS {
+            return Err(Error::InvalidValue);
+        }
+
+        let growth = math::pow(mul_div(BPS + annual_rate_bps, WAD, BPS)?, months as i128, 12)?;
+        mul_div(index, growth, WAD)
+    }
+
+    /// Real rate, in basis points, of a nominal rate after inflation (Fisher equation)
+    pub fn real_rate(nominal_bps: i128, inflation_bps: i128) -> Result<i128, Error> {
+        if inflation_bps <= -BPS {
+            return Err(Error::InvalidValue);
+        }
+
+        Ok(mul_div(BPS + nominal_bps, BPS, BPS + inflation_bps)? - BPS)
+    }
+
+    /// Real return, in basis points, of a nominal APY given a country's latest year-over-year inflation
+    pub fn get_real_return(env: Env, country: Symbol, nominal_apy_bps: i128) -> Result<i128, Error> {
+        let history = storage::read_cpi_history(&env, &country);
+        let latest = history.keys().last().ok_or(Error::PeriodNotFound)?;
+        let inflation = Self::get_yoy_inflation(env, country, latest)?;
+
+        Self::real_rate(nominal_apy_bps, inflation)
+    }
+
+    /// Express an amount in `from` period money in `to` period money using a country's CPI
+    pub fn deflate(env: Env, country: Symbol, amount: i128, from: u32, to: u32) -> Result<i128, Error> {
+        let from_index = read_cpi_index(&env, &country, from)?;
+        let to_index = read_cpi_index(&env, &country, to)?;
+
+        mul_div(amount, to_index, from_index)
+    }
+
+    /// Submit FX rate for a currency pair. The published value is the median of the round.
+    pub fn set_fx(env: Env, reporter: Address, pair: Symbol, rate: i128) {
+        submit_value(&env, &reporter, FeedKind::Fx, &pair, rate);
+    }
+
+    /// Get FX rate for a pair, failing with `FeedNotFound` if none was published
+    pub fn get_fx(env: Env, pair: Symbol) -> Result<i128, Error> {
+        read_published_value(&env, FeedKind::Fx, &pair).map(|rate| rate.value)
+    }
+
+    /// Get FX rate for a pair with the ledger timestamp and sequence of its last update
+    pub fn get_fx_with_timestamp(env: Env, pair: Symbol) -> Result<FeedValue, Error> {
+        read_published_value(&env, FeedKind::Fx, &pair)
+    }
+
+    /// Get FX rate for a pair, failing if it was not updated within `max_age` seconds
+    pub fn get_fx_fresh(env: Env, pair: Symbol, max_age: u64) -> Result<i128, Error> {
+        read_fresh_value(&env, FeedKind::Fx, &pair, max_age)
+    }
+
+    /// Get the time-weighted average FX rate over the last `window` seconds
+    pub fn get_fx_twap(env: Env, pair: Symbol, window: u64) -> Result<i128, Error> {
+        read_twap(&env, FeedKind::Fx, &pair, window)
+    }
+
+    /// Set the base currency cross rates are derived through and the decimals of FX rates (admin only)
+    pub fn set_fx_base(env: Env, base: Symbol, decimals: u32) {
+        require_admin(&env);
+
+        if decimals > MAX_DECIMALS {
+            panic_with_error!(&env, Error::InvalidDecimals);
+        }
+
+        let config = FxConfig { base, decimals };
+        storage::write_fx_config(&env, &config);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_fx_base"),),
+            config,
+        );
+    }
+
+    /// Get the FX base currency and rate decimals
+    pub fn get_fx_base(env: Env) -> Result<FxConfig, Error> {
+        storage::read_fx_config(&env).ok_or(Error::FxBaseNotSet)
+    }
+
+    /// Get the rate from one currency to another, e.g. `EUR` to `NGN`.
+    /// Uses the `EURNGN` pair if published, else inverts `NGNEUR`, else triangulates
+    /// `EUR` to base and base to `NGN`. Rates use the configured fixed-point decimals.
+    /// Paused, retired and premium pairs fail instead of being routed around.
+    pub fn get_cross_rate(env: Env, from: Symbol, to: Symbol) -> Result<i128, Error> {
+        read_cross_rate(&env, &from, &to).map(|rate| rate.value)
+    }
+
+    /// Get the rate from one currency to another with the timestamp and sequence of its oldest pair
+    pub fn get_cross_rate_with_timestamp(env: Env, from: Symbol, to: Symbol) -> Result<FeedValue, Error> {
+        read_cross_rate(&env, &from, &to)
+    }
+
+    /// Get the rate from one currency to another, failing if any pair it uses was not updated
+    /// within `max_age` seconds
+    pub fn get_cross_rate_fresh(env: Env, from: Symbol, to: Symbol, max_age: u64) -> Result<i128, Error> {
+        let rate = read_cross_rate(&env, &from, &to)?;
+        if env.ledger().timestamp().saturating_sub(rate.timestamp) > max_age {
+            return Err(Error::Stale);
+        }
+
+        Ok(rate.value)
+    }
+
+    /// Submit asset price. The published value is the median of the round.
+    pub fn set_asset_price(env: Env, reporter: Address, asset: Symbol, price: i128) {
+        submit_value(&env, &reporter, FeedKind::Asset, &asset, price);
+    }
+
+    /// Get asset price, failing with `FeedNotFound` if none was published
+    pub fn get_asset_price(env: Env, asset: Symbol) -> Result<i128, Error> {
+        read_published_value(&env, FeedKind::Asset, &asset).map(|price| price.value)
+    }
+
+    /// Get asset price with the ledger timestamp and sequence of its last update
+    pub fn get_asset_price_with_timestamp(env: Env, asset: Symbol) -> Result<FeedValue, Error> {
+        read_published_value(&env, FeedKind::Asset, &asset)
+    }
+
+    /// Get asset price, failing if it was not updated within `max_age` seconds
+    pub fn get_asset_price_fresh(env: Env, asset: Symbol, max_age: u64) -> Result<i128, Error> {
+        read_fresh_value(&env, FeedKind::Asset, &asset, max_age)
+    }
+
+    /// Get the time-weighted average asset price over the last `window` seconds
+    pub fn get_asset_twap(env: Env, asset: Symbol, window: u64) -> Result<i128, Error> {
+        read_twap(&env, FeedKind::Asset, &asset, window)
+    }
+
+    /// Set the SEP-40 base asset, price decimals and update resolution in seconds (admin only)
+    pub fn set_price_config(env: Env, base: Asset, decimals: u32, resolution: u32) {
+        require_admin(&env);
+
+        let config = PriceConfig {
+            base,
+            decimals,
+            resolution,
+        };
+        storage::write_price_config(&env, &config);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_price_config"),),
+            config,
+        );
+    }
+
+    /// Map a Stellar asset contract to an asset feed for SEP-40 reads (admin only)
+    pub fn set_asset_address(env: Env, address: Address, asset: Symbol) {
+        require_admin(&env);
+
+        storage::write_asset_address(&env, &address, &asset);
+
+        env.events().publish(
+            (Symbol::new(&env, "set_asset_address"), address),
+            asset,
+        );
+    }
+
+    /// SEP-40: base asset prices are quoted in
+    pub fn base(env: Env) -> Asset {
+        read_price_config(&env).base
+    }
+
+    /// SEP-40: assets with a published price that can be read freely
+    pub fn assets(env: Env) -> Vec<Asset> {
+        let mut assets = Vec::new(&env);
+        for (kind, feed) in storage::read_feeds(&env).iter() {
+            if kind != FeedKind::Asset || require_readable(&env, kind, &feed).is_err() {
+                continue;
+            }
+
+            match storage::read_asset_address(&env, &feed) {
+                Some(address) => assets.push_back(Asset::Stellar(address)),
+                None => assets.push_back(Asset::Other(feed)),
+            }
+        }
+
+        assets
+    }
+
+    /// SEP-40: number of decimals in prices
+    pub fn decimals(env: Env) -> u32 {
+        read_price_config(&env).decimals
+    }
+
+    /// SEP-40: expected seconds between price updates
+    pub fn resolution(env: Env) -> u32 {
+        read_price_config(&env).resolution
+    }
+
+    /// SEP-40: price in effect at `timestamp`, if still in the observation buffer.
+    /// Like all SEP-40 prices, it is rescaled from the feed's registered decimals to `decimals()`.
+    pub fn price(env: Env, asset: Asset, timestamp: u64) -> Option<PriceData> {
+        let feed = resolve_asset(&env, &asset)?;
+        let buffer = storage::read_observations(&env, FeedKind::Asset, &feed)?;
+
+        let mut found = None;
+        for observation in buffer.iter() {
+            if observation.timestamp > timestamp {
+                break;
+            }
+            found = Some(PriceData {
+                price: sep40_price(&env, &feed, observation.price)?,
+                timestamp: observation.timestamp,
+            });
+        }
+
+        found
+    }
+
+    /// SEP-40: up to `records` most recent prices, newest first
+    pub fn prices(env: Env, asset: Asset, records: u32) -> Option<Vec<PriceData>> {
+        let feed = resolve_asset(&env, &asset)?;
+        let buffer = storage::read_observations(&env, FeedKind::Asset, &feed)?;
+
+        let mut prices = Vec::new(&env);
+        for observation in buffer.iter().rev().take(records as usize) {
+            prices.push_back(PriceData {
+                price: sep40_price(&env, &feed, observation.price)?,
+                timestamp: observation.timestamp,
+            });
+        }
+
+        if prices.is_empty() {
+            None
+        } else {
+            Some(prices)
+        }
+    }
+
+    /// SEP-40: most recent price
+    pub fn lastprice(env: Env, asset: Asset) -> Option<PriceData> {
+        let feed = resolve_asset(&env, &asset)?;
+        let value = storage::read_value(&env, FeedKind::Asset, &feed)?;
+        Some(PriceData {
+            price: sep40_price(&env, &feed, value.value)?,
+            timestamp: value.timestamp,
+        })
+    }
+}
+
+/// Storage version written by this code; bump it with each migration `migrate` runs
+const CONTRACT_VERSION: u32 = 2;
+
+const BPS: i128 = 10_000;
+
+/// Most oracles consulted when a feed is missing or stale
+const MAX_FALLBACKS: u32 = 5;
+
+/// Most consumer contracts a single feed notifies
+const MAX_SUBSCRIBERS: u32 = 10;
+
+/// Oldest a signed report may be when relayed, in seconds
+const SIGNED_REPORT_MAX_AGE: u64 = 300;
+
+/// Largest number of decimals a feed may declare
+const MAX_DECIMALS: u32 = 18;
+
+/// Largest whole-unit magnitude accepted for a registered feed, before applying its decimals
+const MAX_WHOLE_UNITS: i128 = 1_000_000_000_000_000_000;
+
+fn require_admin(env: &Env) -> Address {
+    let admin = storage::read_admin(env);
+    admin.require_auth();
+    storage::extend_instance_ttl(env);
+    admin
+}
+
+/// Feed permissions of a reporter, failing if it is not registered
+fn read_registered_reporter(env: &Env, reporter: &Address) -> Vec<(FeedKind, Symbol)> {
+    storage::read_reporter_feeds(env, reporter)
+        .unwrap_or_else(|| panic_with_error!(env, Error::ReporterNotFound))
+}
+
+fn can_report(env: &Env, reporter: &Address, kind: FeedKind, feed: &Symbol) -> bool {
+    match storage::read_reporter_feeds(env, reporter) {
+        Some(feeds) => feeds.contains((kind, feed.clone())),
+        None => false,
+    }
+}
+
+/// Require the reporter's signature and permission to write the feed
+fn require_reporter(env: &Env, reporter: &Address, kind: FeedKind, feed: &Symbol) {
+    reporter.require_auth();
+
+    if !can_report(env, reporter, kind, feed) {
+        panic_with_error!(env, Error::Unauthorized);
+    }
+}
+
+fn read_proposal(env: &Env, country: &Symbol) -> Proposal {
+    storage::read_proposal(env, country)
+        .unwrap_or_else(|| panic_with_error!(env, Error::ProposalNotFound))
+}
+
+/// Annualized growth, in basis points, from one index level to another over a number of months
+fn annualized_rate(from_index: i128, to_index: i128, months: i128) -> Result<i128, Error> {
+    if from_index <= 0 || to_index <= 0 {
+        return Err(Error::InvalidValue);
+    }
+    if months <= 0 {
+        return Err(Error::InvalidPeriod);
+    }
+
+    let growth = math::pow(mul_div(to_index, WAD, from_index)?, 12, months)?;
+    mul_div(growth - WAD, BPS, WAD)
+}
+
+fn read_cpi_index(env: &Env, country: &Symbol, period: u32) -> Result<i128, Error> {
+    require_readable(env, FeedKind::Cpi, country)?;
+    storage::read_cpi_history(env, country)
+        .get(period)
+        .ok_or(Error::PeriodNotFound)
+}
+
+/// A YYYYMM period with a month between 01 and 12 and a year after 0000
+fn is_valid_period(period: u32) -> bool {
+    let month = period % 100;
+    period / 100 > 0 && (1..=12).contains(&month)
+}
+
+/// Topic of the event emitted when a feed kind publishes a value
+fn publish_topic(env: &Env, kind: FeedKind) -> Symbol {
+    match kind {
+        FeedKind::Cpi => Symbol::new(env, "set_cpi"),
+        FeedKind::Fx => Symbol::new(env, "set_fx"),
+        FeedKind::Asset => Symbol::new(env, "set_asset"),
+    }
+}
+
+fn set_feed_status(env: &Env, kind: FeedKind, feed: &Symbol, status: FeedStatus) {
+    require_admin(env);
+
+    if storage::read_feed_status(env, kind, feed) == FeedStatus::Retired {
+        panic_with_error!(env, Error::FeedRetired);
+    }
+
+    storage::write_feed_status(env, kind, feed, status);
+
+    env.events().publish(
+        (Symbol::new(env, "feed_status"), kind, feed.clone()),
+        status,
+    );
+}
+
+/// Fail unless consumers may read the feed for free
+fn require_readable(env: &Env, kind: FeedKind, feed: &Symbol) -> Result<(), Error> {
+    require_free(env, kind, feed)?;
+    require_active(env, kind, feed)
+}
+
+/// Fail if the feed is premium and must be read through `read_premium`
+fn require_free(env: &Env, kind: FeedKind, feed: &Symbol) -> Result<(), Error> {
+    if storage::read_read_fee(env, kind, feed) > 0 {
+        return Err(Error::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Fail if the feed is paused or retired
+fn require_active(env: &Env, kind: FeedKind, feed: &Symbol) -> Result<(), Error> {
+    match storage::read_feed_status(env, kind, feed) {
+        FeedStatus::Active | FeedStatus::Deprecated => Ok(()),
+        FeedStatus::Paused => Err(Error::FeedPaused),
+        FeedStatus::Retired => Err(Error::FeedRetired),
+    }
+}
+
+/// Whether the admin permanently retired the feed
+fn is_retired(env: &Env, kind: FeedKind, feed: &Symbol) -> bool {
+    storage::read_feed_status(env, kind, feed) == FeedStatus::Retired
+}
+
+/// Fail if the feed is a basket, which only publishes values computed from its components
+fn require_not_basket(env: &Env, kind: FeedKind, feed: &Symbol) {
+    if kind == FeedKind::Cpi && storage::read_basket(env, feed).is_some() {
+        panic_with_error!(env, Error::Unauthorized);
+    }
+}
+
+/// Fail unless new values may be submitted to the feed
+fn require_writable(env: &Env, kind: FeedKind, feed: &Symbol) {
+    match storage::read_feed_status(env, kind, feed) {
+        FeedStatus::Active | FeedStatus::Deprecated => {}
+        FeedStatus::Paused => panic_with_error!(env, Error::FeedPaused),
+        FeedStatus::Retired => panic_with_error!(env, Error::FeedRetired),
+    }
+}
+
+fn read_published_value(env: &Env, kind: FeedKind, feed: &Symbol) -> Result<FeedValue, Error> {
+    require_readable(env, kind, feed)?;
+    storage::read_value(env, kind, feed).ok_or(Error::FeedNotFound)
+}
+
+fn read_fresh_value(env: &Env, kind: FeedKind, feed: &Symbol, max_age: u64) -> Result<i128, Error> {
+    let value = read_published_value(env, kind, feed)?;
+    if env.ledger().timestamp().saturating_sub(value.timestamp) > max_age {
+        return Err(Error::Stale);
+    }
+
+    Ok(value.value)
+}
+
+/// Publish a value observed at `timestamp`, which may be older than the current ledger
+fn publish_value(env: &Env, kind: FeedKind, feed: &Symbol, value: i128, confidence: i128, timestamp: u64) {
+    let previous = storage::read_value(env, kind, feed);
+    storage::write_confidence(env, kind, feed, confidence);
+    storage::write_value(
+        env,
+        kind,
+        feed,
+        &FeedValue {
+            value,
+            timestamp,
+            sequence: env.ledger().sequence(),
+        },
+    );
+
+    if kind != FeedKind::Cpi {
+        record_observation(env, kind, feed, value);
+    }
+
+    env.events().publish(
+        (publish_topic(env, kind), feed.clone()),
+        value,
+    );
+
+    if kind == FeedKind::Cpi {
+        for basket in storage::read_baskets_of(env, feed).iter() {
+            recompute_basket(env, &basket);
+        }
+    }
+
+    notify_subscribers(env, kind, feed, previous.map(|previous| previous.value), value);
+}
+
+/// Latest value of a feed at a fallback oracle; `None` if it has none or the call fails
+fn read_fallback(env: &Env, fallback: &Fallback, kind: FeedKind, feed: &Symbol) -> Option<SourcedValue> {
+    match fallback {
+        Fallback::Aureus(oracle) => {
+            let feeds = Vec::from_array(env, [(kind, feed.clone())]);
+            let values = env
+                .try_invoke_contract::<Vec<Option<FeedValue>>, soroban_sdk::Error>(
+                    oracle,
+                    &Symbol::new(env, "get_many"),
+                    (feeds,).into_val(env),
+                )
+                .ok()?
+                .ok()?;
+            let value = values.get(0)??;
+            let decimals = aureus_decimals(env, oracle, kind, feed);
+
+            Some(SourcedValue {
+                value: rescale_fallback(value.value, decimals, local_decimals(env, kind, feed))?,
+                timestamp: value.timestamp,
+                source: oracle.clone(),
+            })
+        }
+        Fallback::Sep40(oracle) if kind == FeedKind::Asset => {
+            let asset = match storage::read_asset_address(env, feed) {
+                Some(address) => Asset::Stellar(address),
+                None => Asset::Other(feed.clone()),
+            };
+            let price = env
+                .try_invoke_contract::<Option<PriceData>, soroban_sdk::Error>(
+                    oracle,
+                    &Symbol::new(env, "lastprice"),
+                    (asset,).into_val(env),
+                )
+                .ok()?
+                .ok()??;
+
+            let decimals = sep40_decimals(env, oracle)?;
+
+            Some(SourcedValue {
+                value: rescale_fallback(price.price, Some(decimals), local_decimals(env, kind, feed))?,
+                timestamp: price.timestamp,
+                source: oracle.clone(),
+            })
+        }
+        Fallback::Sep40(_) => None,
+    }
+}
+
+/// Decimals fallback values are converted to: the feed's registered decimals, else the SEP-40
+/// price decimals for assets
+fn local_decimals(env: &Env, kind: FeedKind, feed: &Symbol) -> Option<u32> {
+    if let Some(metadata) = storage::read_metadata(env, kind, feed) {
+        return Some(metadata.decimals);
+    }
+    if kind != FeedKind::Asset {
+        return None;
+    }
+    storage::read_price_config(env).map(|config| config.decimals)
+}
+
+/// Decimals of a feed at an Aureus fallback, from its registry or else its SEP-40 decimals for assets
+fn aureus_decimals(env: &Env, oracle: &Address, kind: FeedKind, feed: &Symbol) -> Option<u32> {
+    let metadata = env.try_invoke_contract::<FeedMetadata, soroban_sdk::Error>(
+        oracle,
+        &Symbol::new(env, "get_feed_metadata"),
+        (kind, feed.clone()).into_val(env),
+    );
+    if let Ok(Ok(metadata)) = metadata {
+        return Some(metadata.decimals);
+    }
+    if kind != FeedKind::Asset {
+        return None;
+    }
+    sep40_decimals(env, oracle)
+}
+
+/// Price decimals a SEP-40 oracle reports, if the call succeeds
+fn sep40_decimals(env: &Env, oracle: &Address) -> Option<u32> {
+    env.try_invoke_contract::<u32, soroban_sdk::Error>(oracle, &Symbol::new(env, "decimals"), Vec::new(env))
+        .ok()?
+        .ok()
+}
+
+/// Convert a fallback value between decimals, leaving it as is unless both are known
+fn rescale_fallback(value: i128, from: Option<u32>, to: Option<u32>) -> Option<i128> {
+    match (from, to) {
+        (Some(from), Some(to)) => rescale(value, from, to),
+        _ => Some(value),
+    }
+}
+
+/// Convert an asset feed's price from its registered decimals to the SEP-40 price decimals
+fn sep40_price(env: &Env, feed: &Symbol, price: i128) -> Option<i128> {
+    match (storage::read_metadata(env, FeedKind::Asset, feed), storage::read_price_config(env)) {
+        (Some(metadata), Some(config)) => rescale(price, metadata.decimals, config.decimals),
+        _ => Some(price),
+    }
+}
+
+/// Convert a fixed-point value between decimals, truncating extra digits
+fn rescale(value: i128, from: u32, to: u32) -> Option<i128> {
+    if from > to {
+        Some(value / 10i128.checked_pow(from - to)?)
+    } else {
+        value.checked_mul(10i128.checked_pow(to - from)?)
+    }
+}
+
+/// Publish the weighted CPI of an active basket once every component has an active value with
+/// the basket's decimals. The basket is as old as its stalest component, and is left as is if
+/// the weighted sum overflows.
+fn recompute_basket(env: &Env, basket: &Symbol) {
+    let Some(components) = storage::read_basket(env, basket) else {
+        return;
+    };
+    if require_active(env, FeedKind::Cpi, basket).is_err() || !basket_decimals_match(env, basket, &components) {
+        return;
+    }
+
+    let mut value = 0;
+    let mut confidence = 0;
+    let mut timestamp = u64::MAX;
+    for (country, weight) in components.iter() {
+        if require_active(env, FeedKind::Cpi, &country).is_err() {
+            return;
+        }
+        let Some(component) = storage::read_value(env, FeedKind::Cpi, &country) else {
+            return;
+        };
+
+        let weighted = |amount: i128, total: i128| amount.checked_mul(weight as i128)?.checked_add(total);
+        let (Some(next_value), Some(next_confidence)) = (
+            weighted(component.value, value),
+            weighted(storage::read_confidence(env, FeedKind::Cpi, &country), confidence),
+        ) else {
+            return;
+        };
+        value = next_value;
+        confidence = next_confidence;
+        timestamp = timestamp.min(component.timestamp);
+    }
+
+    publish_value(env, FeedKind::Cpi, basket, value / BPS, confidence / BPS, timestamp);
+}
+
+/// Whether a basket's components, and the basket if registered, declare the same decimals
+fn basket_decimals_match(env: &Env, basket: &Symbol, components: &Vec<(Symbol, u32)>) -> bool {
+    let decimals = |feed: &Symbol| storage::read_metadata(env, FeedKind::Cpi, feed).map(|metadata| metadata.decimals);
+
+    let expected = components.first().and_then(|(country, _)| decimals(&country));
+    components.iter().all(|(country, _)| decimals(&country) == expected)
+        && decimals(basket).is_none_or(|registered| Some(registered) == expected)
+}
+
+/// Stop recomputing a basket when its former components publish
+fn unlink_basket(env: &Env, basket: &Symbol, components: &Vec<(Symbol, u32)>) {
+    for (country, _) in components.iter() {
+        let mut baskets = storage::read_baskets_of(env, &country);
+        if let Some(index) = baskets.first_index_of(basket.clone()) {
+            baskets.remove(index);
+            storage::write_baskets_of(env, &country, &baskets);
+        }
+    }
+}
+
+fn subscription_index(subscriptions: &Vec<Subscription>, consumer: &Address) -> Option<u32> {
+    subscriptions
+        .iter()
+        .position(|subscription| subscription.consumer == *consumer)
+        .map(|index| index as u32)
+}
+
+fn trigger_fires(subscription: &Subscription, previous: Option<i128>, value: i128) -> bool {
+    match subscription.trigger {
+        Trigger::Any => true,
+        Trigger::Threshold(threshold) => match previous {
+            Some(previous) => (previous < threshold) != (value < threshold),
+            None => false,
+        },
+        Trigger::Deviation(bps) => {
+            let last = subscription.last_notified;
+            last == 0 || (value - last).abs() * BPS >= last.abs() * bps as i128
+        }
+    }
+}
+
+/// Call `on_oracle_update` on subscribers whose trigger fires. A failing consumer does not
+/// block the update; it is reported with a `notify_failed` event instead. Premium feeds only
+/// notify whitelisted free readers.
+fn notify_subscribers(env: &Env, kind: FeedKind, feed: &Symbol, previous: Option<i128>, value: i128) {
+    let mut subscriptions = storage::read_subscriptions(env, kind, feed);
+    if subscriptions.is_empty() {
+        return;
+    }
+
+    let premium = storage::read_read_fee(env, kind, feed) > 0;
+    let callback = Symbol::new(env, "on_oracle_update");
+    let mut notified = false;
+    for index in 0..subscriptions.len() {
+        let mut subscription = subscriptions.get_unchecked(index);
+        if premium && !storage::read_free_reader(env, &subscription.consumer) {
+            continue;
+        }
+        if !trigger_fires(&subscription, previous, value) {
+            continue;
+        }
+
+        let args: Vec<Val> = (feed.clone(), value).into_val(env);
+        let result = env.try_invoke_contract::<(), soroban_sdk::Error>(&subscription.consumer, &callback, args);
+        if !matches!(result, Ok(Ok(()))) {
+            env.events().publish(
+                (Symbol::new(env, "notify_failed"), kind, feed.clone()),
+                subscription.consumer.clone(),
+            );
+        }
+
+        subscription.last_notified = value;
+        subscriptions.set(index, subscription);
+        notified = true;
+    }
+
+    if notified {
+        storage::write_subscriptions(env, kind, feed, &subscriptions);
+    }
+}
+
+/// Append a price to the feed's observation buffer, accumulating the previous price over the elapsed time
+fn record_observation(env: &Env, kind: FeedKind, feed: &Symbol, price: i128) {
+    let now = env.ledger().timestamp();
+    let mut buffer = storage::read_observations(env, kind, feed).unwrap_or(Vec::new(env));
+
+    let cumulative = match buffer.last() {
+        Some(last) if last.timestamp == now => {
+            // A later update in the same ledger replaces the earlier one
+            buffer.pop_back();
+            last.cumulative
+        }
+        Some(last) => last.cumulative + last.price * (now - last.timestamp) as i128,
+        None => 0,
+    };
+
+    buffer.push_back(Observation {
+        timestamp: now,
+        price,
+        cumulative,
+    });
+
+    while buffer.len() > storage::read_observation_limit(env) {
+        buffer.pop_front();
+    }
+
+    storage::write_observations(env, kind, feed, &buffer);
+}
+
+/// Cumulative price at `timestamp`, extrapolated from the latest observation at or before it
+fn cumulative_at(buffer: &Vec<Observation>, timestamp: u64) -> Option<i128> {
+    let mut found = None;
+    for observation in buffer.iter() {
+        if observation.timestamp > timestamp {
+            break;
+        }
+        found = Some(observation);
+    }
+
+    found.map(|observation| {
+        observation.cumulative + observation.price * (timestamp - observation.timestamp) as i128
+    })
+}
+
+/// Asset feed backing a SEP-40 asset
+fn resolve_asset(env: &Env, asset: &Asset) -> Option<Symbol> {
+    let feed = match asset {
+        Asset::Stellar(address) => storage::read_asset_feed(env, address)?,
+        Asset::Other(feed) => feed.clone(),
+    };
+
+    require_readable(env, FeedKind::Asset, &feed).ok()?;
+    Some(feed)
+}
+
+fn read_twap(env: &Env, kind: FeedKind, feed: &Symbol, window: u64) -> Result<i128, Error> {
+    if window == 0 {
+        return Err(Error::InvalidWindow);
+    }
+    require_readable(env, kind, feed)?;
+
+    let buffer = storage::read_observations(env, kind, feed).ok_or(Error::FeedNotFound)?;
+
+    let now = env.ledger().timestamp();
+    let start = now.checked_sub(window).ok_or(Error::InsufficientHistory)?;
+    let start_cumulative = cumulative_at(&buffer, start).ok_or(Error::InsufficientHistory)?;
+    let end_cumulative = cumulative_at(&buffer, now).ok_or(Error::InsufficientHistory)?;
+
+    Ok((end_cumulative - start_cumulative) / window as i128)
+}
+
+/// Reject values of a registered feed that are not positive or exceed its declared scale
+fn require_valid_value(env: &Env, kind: FeedKind, feed: &Symbol, value: i128) {
+    if let Some(metadata) = storage::read_metadata(env, kind, feed) {
+        let max = MAX_WHOLE_UNITS * 10i128.pow(metadata.decimals);
+        if value <= 0 || value > max {
+            panic_with_error!(env, Error::InvalidValue);
+        }
+    }
+}
+
+fn submit_value(env: &Env, reporter: &Address, kind: FeedKind, feed: &Symbol, value: i128) {
+    reporter.require_auth();
+    record_submission(env, reporter, kind, feed, value, 0, env.ledger().timestamp());
+}
+
+/// Record an authenticated reporter's value observed at `observed_at` in the feed's open round,
+/// publishing the median once quorum is reached
+fn record_submission(
+    env: &Env,
+    reporter: &Address,
+    kind: FeedKind,
+    feed: &Symbol,
+    value: i128,
+    confidence: i128,
+    observed_at: u64,
+) {
+    if !can_report(env, reporter, kind, feed) {
+        panic_with_error!(env, Error::Unauthorized);
+    }
+    if storage::read_commit_reveal_config(env).is_some() {
+        panic_with_error!(env, Error::CommitRevealRequired);
+    }
+    require_not_basket(env, kind, feed);
+    require_writable(env, kind, feed);
+    require_stake(env, reporter);
+    require_valid_value(env, kind, feed, value);
+
+    if storage::read_pending(env, kind, feed).is_some() {
+        panic_with_error!(env, Error::FeedTripped);
+    }
+
+    let mut round = storage::read_round(env, kind, feed);
+    let mut confidences = storage::read_round_confidences(env, kind, feed);
+    let now = env.ledger().timestamp();
+    if !round.submissions.is_empty() && now - round.opened_at > storage::read_round_max_age(env) {
+        env.events().publish(
+            (Symbol::new(env, "round_expired"), kind, feed.clone()),
+            (round.id, round.submissions.len()),
+        );
+        round = Round::new(env, round.id + 1);
+        confidences = Map::new(env);
+    }
+    drop_ineligible(env, kind, feed, &mut round, &mut confidences);
+
+    if round.submissions.contains_key(reporter.clone()) {
+        panic_with_error!(env, Error::AlreadySubmitted);
+    }
+
+    if round.submissions.is_empty() {
+        round.opened_at = now;
+        round.observed_at = observed_at;
+    }
+    round.observed_at = round.observed_at.min(observed_at);
+    round.submissions.set(reporter.clone(), value);
+
+    if confidence > 0 {
+        confidences.set(reporter.clone(), confidence);
+    }
+
+    env.events().publish(
+        (Symbol::new(env, "submit"), kind, feed.clone()),
+        (reporter.clone(), round.id, value),
+    );
+
+    if round.submissions.len() >= storage::read_quorum(env) {
+        close_round(env, kind, feed, &round, &confidences);
+        round = Round::new(env, round.id + 1);
+        confidences = Map::new(env);
+    }
+
+    storage::write_round(env, kind, feed, &round);
+    storage::write_round_confidences(env, kind, feed, &confidences);
+    storage::extend_instance_ttl(env);
+}
+
+/// Drop the submissions of reporters that were removed or lost the feed since submitting
+fn drop_ineligible(env: &Env, kind: FeedKind, feed: &Symbol, round: &mut Round, confidences: &mut Map<Address, i128>) {
+    for reporter in round.submissions.keys().iter() {
+        if !can_report(env, &reporter, kind, feed) {
+            round.submissions.remove(reporter.clone());
+            confidences.remove(reporter);
+        }
+    }
+}
+
+/// Publish the median of a round that reached quorum, or hold it if it trips the circuit breaker
+fn close_round(env: &Env, kind: FeedKind, feed: &Symbol, round: &Round, confidences: &Map<Address, i128>) {
+    let median = median(round.submissions.values());
+    let confidence = round_confidence(round, confidences, median);
+    slash_outliers(env, round, median);
+    storage::write_participants(env, kind, feed, round.submissions.len());
+    publish_or_hold(env, kind, feed, median, confidence, round.observed_at);
+}
+
+/// Require that a feed is writable and not held by its circuit breaker
+fn require_accepts_value(env: &Env, kind: FeedKind, feed: &Symbol) {
+    require_writable(env, kind, feed);
+    if storage::read_pending(env, kind, feed).is_some() {
+        panic_with_error!(env, Error::FeedTripped);
+    }
+}
+
+/// Publish a value observed at `timestamp`, or hold it if it trips the circuit breaker
+fn publish_or_hold(env: &Env, kind: FeedKind, feed: &Symbol, value: i128, confidence: i128, timestamp: u64) {
+    if exceeds_max_deviation(env, kind, feed, value) {
+        hold_pending(env, kind, feed, value, confidence, timestamp);
+    } else {
+        publish_value(env, kind, feed, value, confidence, timestamp);
+    }
+}
+
+/// Require the configured minimum stake, if staking is enabled
+fn require_stake(env: &Env, reporter: &Address) {
+    if let Some(config) = storage::read_stake_config(env) {
+        if storage::read_stake(env, reporter) < config.min_stake {
+            panic_with_error!(env, Error::InsufficientStake);
+        }
+    }
+}
+
+fn read_fee_config(env: &Env) -> FeeConfig {
+    storage::read_fee_config(env).unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+}
+
+/// Charge a read fee to the consumer's credit, or transfer it from the consumer if credit is short
+fn charge_read_fee(env: &Env, consumer: &Address, fee: i128) {
+    let credit = storage::read_credit(env, consumer);
+    if credit >= fee {
+        storage::write_credit(env, consumer, credit - fee);
+    } else {
+        let config = read_fee_config(env);
+        token::Client::new(env, &config.token).transfer(consumer, &env.current_contract_address(), &fee);
+    }
+
+    storage::write_fee_balance(env, storage::read_fee_balance(env) + fee);
+}
+
+fn read_price_config(env: &Env) -> PriceConfig {
+    storage::read_price_config(env).unwrap_or_else(|| panic_with_error!(env, Error::PriceConfigNotSet))
+}
+
+fn read_stake_config(env: &Env) -> StakeConfig {
+    storage::read_stake_config(env)
+        .unwrap_or_else(|| panic_with_error!(env, Error::StakeNotConfigured))
+}
+
+/// Move up to `amount` of a reporter's stake to the admin, returning the amount moved
+fn slash_stake(env: &Env, config: &StakeConfig, reporter: &Address, amount: i128) -> i128 {
+    // Bonded stake is slashed first, then stake that is still unbonding
+    let staked = storage::read_stake(env, reporter);
+    let mut unbonding = storage::read_unbonding(env, reporter);
+    let from_stake = amount.min(staked);
+    let from_unbonding = (amount - from_stake).min(unbonding.amount);
+    let slashed = from_stake + from_unbonding;
+    if slashed == 0 {
+        return 0;
+    }
+
+    storage::write_stake(env, reporter, staked - from_stake);
+    if from_unbonding > 0 {
+        unbonding.amount -= from_unbonding;
+        if unbonding.amount == 0 {
+            storage::remove_unbonding(env, reporter);
+        } else {
+            storage::write_unbonding(env, reporter, &unbonding);
+        }
+    }
+    token::Client::new(env, &config.token).transfer(
+        &env.current_contract_address(),
+        &storage::read_admin(env),
+        &slashed,
+    );
+
+    env.events().publish(
+        (Symbol::new(env, "slash"), reporter.clone()),
+        slashed,
+    );
+
+    slashed
+}
+
+/// Slash reporters whose submission in a closed round is further from its median than staking allows
+fn slash_outliers(env: &Env, round: &Round, median: i128) {
+    let config = match storage::read_stake_config(env) {
+        Some(config) if config.outlier_bps > 0 && config.outlier_slash > 0 => config,
+        _ => return,
+    };
+
+    for (reporter, value) in round.submissions.iter() {
+        if (value - median).abs() * BPS > median.abs() * config.outlier_bps as i128 {
+            slash_stake(env, &config, &reporter, config.outlier_slash);
+        }
+    }
+}
+
+/// Pair symbol quoting `to` per unit of `from`, e.g. `EURUSD`
+fn pair_symbol(env: &Env, from: &Symbol, to: &Symbol) -> Result<Symbol, Error> {
+    let from = SymbolStr::try_from_val(env, &from.to_symbol_val()).map_err(|_| Error::InvalidCurrency)?;
+    let to = SymbolStr::try_from_val(env, &to.to_symbol_val()).map_err(|_| Error::InvalidCurrency)?;
+    let (from, to): (&[u8], &[u8]) = (from.as_ref(), to.as_ref());
+
+    let mut pair = [0u8; 32];
+    let len = from.len() + to.len();
+    if from.is_empty() || to.is_empty() || len > pair.len() {
+        return Err(Error::InvalidCurrency);
+    }
+    pair[..from.len()].copy_from_slice(from);
+    pair[from.len()..len].copy_from_slice(to);
+
+    let pair = core::str::from_utf8(&pair[..len]).map_err(|_| Error::InvalidCurrency)?;
+    Ok(Symbol::new(env, pair))
+}
+
+/// Rate from one currency to another directly, inversely or through the base currency,
+/// dated by the oldest pair it uses
+fn read_cross_rate(env: &Env, from: &Symbol, to: &Symbol) -> Result<FeedValue, Error> {
+    let config = storage::read_fx_config(env).ok_or(Error::FxBaseNotSet)?;
+    let one = 10i128.pow(config.decimals);
+
+    if let Some(rate) = read_pair_rate(env, from, to, config.decimals)? {
+        return Ok(rate);
+    }
+
+    let to_base = read_pair_rate(env, from, &config.base, config.decimals)?.ok_or(Error::NoFxPath)?;
+    let from_base = read_pair_rate(env, &config.base, to, config.decimals)?.ok_or(Error::NoFxPath)?;
+    let oldest = if to_base.timestamp <= from_base.timestamp { &to_base } else { &from_base };
+
+    Ok(FeedValue {
+        value: mul_div(to_base.value, from_base.value, one)?,
+        timestamp: oldest.timestamp,
+        sequence: oldest.sequence,
+    })
+}
+
+/// Rate from one currency to another with `decimals` decimals, using the direct pair or the
+/// inverse of the reverse pair. Only unpublished pairs are skipped; lifecycle and read fee errors are returned.
+fn read_pair_rate(env: &Env, from: &Symbol, to: &Symbol, decimals: u32) -> Result<Option<FeedValue>, Error> {
+    let one = 10i128.pow(decimals);
+    if from == to {
+        return Ok(Some(FeedValue {
+            value: one,
+            timestamp: env.ledger().timestamp(),
+            sequence: env.ledger().sequence(),
+        }));
+    }
+
+    match read_pair_value(env, &pair_symbol(env, from, to)?, decimals) {
+        Ok(rate) => return Ok(Some(rate)),
+        Err(Error::FeedNotFound) => {}
+        Err(error) => return Err(error),
+    }
+
+    match read_pair_value(env, &pair_symbol(env, to, from)?, decimals) {
+        Ok(rate) if rate.value > 0 => Ok(Some(FeedValue {
+            value: mul_div(one, one, rate.value)?,
+            ..rate
+        })),
+        Ok(_) | Err(Error::FeedNotFound) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Published value of an FX pair converted from its registered decimals to `decimals`
+fn read_pair_value(env: &Env, pair: &Symbol, decimals: u32) -> Result<FeedValue, Error> {
+    let rate = read_published_value(env, FeedKind::Fx, pair)?;
+    let Some(metadata) = storage::read_metadata(env, FeedKind::Fx, pair) else {
+        return Ok(rate);
+    };
+
+    Ok(FeedValue {
+        value: rescale(rate.value, metadata.decimals, decimals).ok_or(Error::Overflow)?,
+        ..rate
+    })
+}
+
+/// Whether a new value moves further from the published one than the feed's circuit breaker allows
+fn exceeds_max_deviation(env: &Env, kind: FeedKind, feed: &Symbol, value: i128) -> bool {
+    let max_bps = storage::read_max_deviation(env, kind, feed);
+    if max_bps == 0 {
+        return false;
+    }
+
+    match storage::read_value(env, kind, feed) {
+        Some(previous) => deviates(max_bps, previous.value, value),
+        None => false,
+    }
+}
+
+/// Whether a value moved further from the previous one than `max_bps` allows; zero disables the check
+fn deviates(max_bps: u32, previous: i128, value: i128) -> bool {
+    max_bps != 0
+        && previous != 0
+        && (value - previous).abs() * BPS > previous.abs() * max_bps as i128
+}
+
+/// Trip the feed's circuit breaker, holding the value until an admin confirms or rejects it
+fn hold_pending(env: &Env, kind: FeedKind, feed: &Symbol, value: i128, confidence: i128, timestamp: u64) {
+    storage::write_pending_confidence(env, kind, feed, confidence);
+    storage::write_pending(
+        env,
+        kind,
+        feed,
+        &FeedValue {
+            value,
+            timestamp,
+            sequence: env.ledger().sequence(),
+        },
+    );
+
+    env.events().publish(
+        (Symbol::new(env, "feed_tripped"), kind, feed.clone()),
+        value,
+    );
+}
+
+/// Discard the value held by a tripped feed and reset its circuit breaker
+fn discard_pending(env: &Env, kind: FeedKind, feed: &Symbol) {
+    let pending = take_pending(env, kind, feed);
+    storage::remove_pending_confidence(env, kind, feed);
+
+    env.events().publish(
+        (Symbol::new(env, "reject_pending"), kind, feed.clone()),
+        pending.value,
+    );
+}
+
+fn take_pending(env: &Env, kind: FeedKind, feed: &Symbol) -> FeedValue {
+    let value = storage::read_pending(env, kind, feed)
+        .unwrap_or_else(|| panic_with_error!(env, Error::NoPendingValue));
+
+    storage::remove_pending(env, kind, feed);
+
+    value
+}
+
+/// Confidence of a closed round: the wider of the reporters' median stated uncertainty
+/// and the median absolute deviation of their values from the round median
+fn round_confidence(round: &Round, confidences: &Map<Address, i128>, median_value: i128) -> i128 {
+    let env = confidences.env();
+    let mut deviations = Vec::new(env);
+    let mut stated = Vec::new(env);
+    for (reporter, value) in round.submissions.iter() {
+        deviations.push_back((value - median_value).abs());
+        stated.push_back(confidences.get(reporter).unwrap_or(0));
+    }
+
+    median(deviations).max(median(stated))
+}
+
+/// Median of the values; the mean of the two middle values for an even count
+fn median(values: Vec<i128>) -> i128 {
+    let mut sorted: Vec<i128> = Vec::new(values.env());
+    for value in values.iter() {
+        let mut index = 0;
+        while index < sorted.len() && sorted.get_unchecked(index) < value {
+            index += 1;
+        }
+        sorted.insert(index, value);
+    }
+
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        let low = sorted.get_unchecked(mid - 1);
+        let high = sorted.get_unchecked(mid);
+        low + (high - low) / 2
+    } else {
+        sorted.get_unchecked(mid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use soroban_sdk::testutils::{Address as _, Ledger};
+
+    fn setup(env: &Env) -> (OracleContractClient<'_>, Address, Address) {
+        env.mock_all_auths();
+
+        let contract_id = env.register_contract(None, OracleContract);
+        let client = OracleContractClient::new(env, &contract_id);
+
+        let admin = Address::generate(env);
+        let reporter = Address::generate(env);
+        client.init(&admin);
+        client.add_reporter(&reporter);
+
+        (client, admin, reporter)
+    }
+
+    fn add_cpi_reporter(env: &Env, client: &OracleContractClient, country: &Symbol) -> Address {
+        let reporter = Address::generate(env);
+        client.add_reporter(&reporter);
+        client.allow_feed(&reporter, &FeedKind::Cpi, country);
+        reporter
+    }
+
+    #[test]
+    fn test_set_get_cpi() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let country = Symbol::new(&env, "USA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &country);
+        client.set_cpi(&reporter, &country, &250);
+
+        assert_eq!(client.get_cpi(&country), 250);
+    }
+
+    #[test]
+    fn test_set_get_fx() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let pair = Symbol::new(&env, "USDNGN");
+        client.allow_feed(&reporter, &FeedKind::Fx, &pair);
+        client.set_fx(&reporter, &pair, &1000);
+
+        assert_eq!(client.get_fx(&pair), 1000);
+    }
+
+    #[test]
+    fn test_set_get_asset_price() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let asset = Symbol::new(&env, "GOLD");
+        client.allow_feed(&reporter, &FeedKind::Asset, &asset);
+        client.set_asset_price(&reporter, &asset, &2000);
+
+        assert_eq!(client.get_asset_price(&asset), 2000);
+    }
+
+    #[test]
+    fn test_unregistered_reporter_cannot_write() {
+        let env = Env::default();
+        let (client, _, _) = setup(&env);
+
+        let outsider = Address::generate(&env);
+        assert_eq!(
+            client.try_set_cpi(&outsider, &Symbol::new(&env, "USA"), &250),
+            Err(Ok(Error::Unauthorized.into()))
+        );
+    }
+
+    #[test]
+    fn test_reporter_cannot_write_other_feed() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        client.allow_feed(&reporter, &FeedKind::Cpi, &Symbol::new(&env, "USA"));
+        assert_eq!(
+            client.try_set_cpi(&reporter, &Symbol::new(&env, "NGA"), &250),
+            Err(Ok(Error::Unauthorized.into()))
+        );
+    }
+
+    #[test]
+    fn test_removed_reporter_loses_permissions() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let country = Symbol::new(&env, "USA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &country);
+        assert!(client.can_report(&reporter, &FeedKind::Cpi, &country));
+
+        client.remove_reporter(&reporter);
+
+        assert!(!client.is_reporter(&reporter));
+        assert!(!client.can_report(&reporter, &FeedKind::Cpi, &country));
+        assert_eq!(client.try_set_cpi(&reporter, &country, &250), Err(Ok(Error::Unauthorized.into())));
+    }
+
+    #[test]
+    fn test_revoke_feed() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let pair = Symbol::new(&env, "USDNGN");
+        client.allow_feed(&reporter, &FeedKind::Fx, &pair);
+        client.revoke_feed(&reporter, &FeedKind::Fx, &pair);
+
+        assert_eq!(client.get_reporter_feeds(&reporter).len(), 0);
+        assert_eq!(client.try_set_fx(&reporter, &pair, &1000), Err(Ok(Error::Unauthorized.into())));
+    }
+
+    #[test]
+    fn test_reporter_management_requires_admin() {
+        let env = Env::default();
+        let contract_id = env.register_contract(None, OracleContract);
+        let client = OracleContractClient::new(&env, &contract_id);
+
+        // No auths are mocked, so the admin signature is missing
+        let admin = Address::generate(&env);
+        assert!(client.try_init(&admin).is_err());
+
+        env.mock_all_auths();
+        client.init(&admin);
+        env.set_auths(&[]);
+        assert!(client.try_add_reporter(&Address::generate(&env)).is_err());
+    }
+
+    #[test]
+    fn test_init_twice() {
+        let env = Env::default();
+        let (client, admin, _) = setup(&env);
+
+        assert_eq!(client.try_init(&admin), Err(Ok(Error::AlreadyInitialized.into())));
+    }
+
+    #[test]
+    fn test_median_published_once_quorum_reached() {
+        let env = Env::default();
+        let (client, _, _) = setup(&env);
+
+        let country = Symbol::new(&env, "NGA");
+        let first = add_cpi_reporter(&env, &client, &country);
+        let second = add_cpi_reporter(&env, &client, &country);
+        let third = add_cpi_reporter(&env, &client, &country);
+        client.set_quorum(&3);
+
+        client.set_cpi(&first, &country, &300);
+        client.set_cpi(&second, &country, &250);
+        assert_eq!(client.try_get_cpi(&country), Err(Ok(Error::FeedNotFound)));
+        assert_eq!(client.get_round(&FeedKind::Cpi, &country).submissions.len(), 2);
+
+        client.set_cpi(&third, &country, &260);
+        assert_eq!(client.get_cpi(&country), 260);
+
+        let round = client.get_round(&FeedKind::Cpi, &country);
+        assert_eq!(round.id, 1);
+        assert_eq!(round.submissions.len(), 0);
+    }
+
+    #[test]
+    fn test_rounds_expire_and_drop_removed_reporters() {
+        let env = Env::default();
+        let (client, _, _) = setup(&env);
+
+        let country = Symbol::new(&env, "NGA");
+        let first = add_cpi_reporter(&env, &client, &country);
+        let second = add_cpi_reporter(&env, &client, &country);
+        let third = add_cpi_reporter(&env, &client, &country);
+        client.set_quorum(&2);
+        client.set_round_max_age(&3_600);
+
+        // A submission older than the round's max age is dropped, not medianed with new ones
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_cpi(&first, &country, &100);
+        env.ledger().with_mut(|li| li.timestamp = 5_000);
+        client.set_cpi(&second, &country, &300);
+        assert_eq!(client.try_get_cpi(&country), Err(Ok(Error::FeedNotFound)));
+        let round = client.get_round(&FeedKind::Cpi, &country);
+        assert_eq!(round.id, 1);
+        assert_eq!(round.opened_at, 5_000);
+        assert_eq!(round.submissions.len(), 1);
+
+        // A removed reporter's submission does not count toward quorum
+        client.remove_reporter(&second);
+        client.set_cpi(&third, &country, &310);
+        assert_eq!(client.try_get_cpi(&country), Err(Ok(Error::FeedNotFound)));
+        client.set_cpi(&first, &country, &320);
+        assert_eq!(client.get_cpi(&country), 315);
+    }
+
+    #[test]
+    fn test_median_of_even_quorum() {
+        let env = Env::default();
+        let (client, _, _) = setup(&env);
+
+        let country = Symbol::new(&env, "NGA");
+        let first = add_cpi_reporter(&env, &client, &country);
+        let second = add_cpi_reporter(&env, &client, &country);
+        client.set_quorum(&2);
+
+        client.set_cpi(&first, &country, &200);
+        client.set_cpi(&second, &country, &100);
+
+        assert_eq!(client.get_cpi(&country), 150);
+    }
+
+    #[test]
+    fn test_reporter_submits_once_per_round() {
+        let env = Env::default();
+        let (client, _, _) = setup(&env);
+
+        let country = Symbol::new(&env, "NGA");
+        let reporter = add_cpi_reporter(&env, &client, &country);
+        client.set_quorum(&2);
+
+        client.set_cpi(&reporter, &country, &200);
+        assert_eq!(client.try_set_cpi(&reporter, &country, &900), Err(Ok(Error::AlreadySubmitted.into())));
+    }
+
+    #[test]
+    fn test_values_carry_ledger_timestamp() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let pair = Symbol::new(&env, "USDNGN");
+        client.allow_feed(&reporter, &FeedKind::Fx, &pair);
+        assert_eq!(client.try_get_fx_with_timestamp(&pair), Err(Ok(Error::FeedNotFound)));
+
+        env.ledger().with_mut(|li| {
+            li.timestamp = 1_000;
+            li.sequence_number = 42;
+        });
+        client.set_fx(&reporter, &pair, &1500);
+
+        let rate = client.get_fx_with_timestamp(&pair);
+        assert_eq!(rate.value, 1500);
+        assert_eq!(rate.timestamp, 1_000);
+        assert_eq!(rate.sequence, 42);
+    }
+
+    #[test]
+    fn test_fresh_getter_rejects_stale_data() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let country = Symbol::new(&env, "USA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &country);
+        assert_eq!(client.try_get_cpi_fresh(&country, &3_600), Err(Ok(Error::FeedNotFound)));
+
+        env.ledger().with_mut(|li| li.timestamp = 10_000);
+        client.set_cpi(&reporter, &country, &250);
+        assert_eq!(client.get_cpi_fresh(&country, &3_600), 250);
+
+        env.ledger().with_mut(|li| li.timestamp = 10_000 + 3_601);
+        assert_eq!(client.try_get_cpi_fresh(&country, &3_600), Err(Ok(Error::Stale)));
+    }
+
+    #[test]
+    fn test_missing_feed_is_distinguishable_from_zero() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let asset = Symbol::new(&env, "GOLD");
+        assert_eq!(client.try_get_asset_price(&asset), Err(Ok(Error::FeedNotFound)));
+
+        client.allow_feed(&reporter, &FeedKind::Asset, &asset);
+        client.set_asset_price(&reporter, &asset, &0);
+        assert_eq!(client.try_get_asset_price(&asset), Ok(Ok(0)));
+    }
+
+    #[test]
+    fn test_cpi_history_and_yoy_inflation() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let country = Symbol::new(&env, "NGA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &country);
+        client.record_cpi(&reporter, &country, &202401, &1_000);
+        client.record_cpi(&reporter, &country, &202406, &1_100);
+        client.record_cpi(&reporter, &country, &202501, &1_250);
+
+        assert_eq!(client.get_cpi_at(&country, &202406), 1_100);
+        assert_eq!(client.get_cpi_range(&country, &202402, &202501).len(), 2);
+        assert_eq!(client.get_yoy_inflation(&country, &202501), 2_500);
+        assert_eq!(
+            client.try_get_yoy_inflation(&country, &202406),
+            Err(Ok(Error::PeriodNotFound))
+        );
+
+        // Inflation too large for basis points fails instead of overflowing
+        let ghana = Symbol::new(&env, "GHA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &ghana);
+        client.record_cpi(&reporter, &ghana, &202401, &1);
+        client.record_cpi(&reporter, &ghana, &202501, &(i128::MAX / 2));
+        assert_eq!(
+            client.try_get_yoy_inflation(&ghana, &202501),
+            Err(Ok(Error::Overflow))
+        );
+
+        // History goes through the same guards as published values
+        client.set_max_deviation(&FeedKind::Cpi, &country, &2_000);
+        assert_eq!(
+            client.try_record_cpi(&reporter, &country, &202502, &2_000),
+            Err(Ok(Error::DeviationTooLarge.into()))
+        );
+        client.record_cpi(&reporter, &country, &202502, &1_260);
+        client.pause_feed(&FeedKind::Cpi, &country);
+        assert_eq!(
+            client.try_record_cpi(&reporter, &country, &202503, &1_270),
+            Err(Ok(Error::FeedPaused.into()))
+        );
+    }
+
+    #[test]
+    fn test_cpi_history_is_bounded() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let country = Symbol::new(&env, "USA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &country);
+        client.set_history_limit(&2);
+
+        client.record_cpi(&reporter, &country, &202403, &310);
+        client.record_cpi(&reporter, &country, &202401, &300);
+        client.record_cpi(&reporter, &country, &202402, &305);
+
+        let range = client.get_cpi_range(&country, &0, &u32::MAX);
+        assert_eq!(range.len(), 2);
+        assert_eq!(range.get_unchecked(0), (202402, 305));
+        assert_eq!(
+            client.try_record_cpi(&reporter, &country, &202413, &320),
+            Err(Ok(Error::InvalidPeriod.into()))
+        );
+    }
+
+    #[test]
+    fn test_twap_weights_prices_by_time() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let pair = Symbol::new(&env, "USDNGN");
+        client.allow_feed(&reporter, &FeedKind::Fx, &pair);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_fx(&reporter, &pair, &1_000);
+        env.ledger().with_mut(|li| li.timestamp = 4_000);
+        client.set_fx(&reporter, &pair, &2_000);
+        env.ledger().with_mut(|li| li.timestamp = 5_000);
+
+        // 3000s at 1000 and 1000s at 2000
+        assert_eq!(client.get_fx_twap(&pair, &4_000), 1_250);
+        // Only the last 1000s at 2000
+        assert_eq!(client.get_fx_twap(&pair, &1_000), 2_000);
+        // A spot update only weighs in from the moment it is published
+        client.set_fx(&reporter, &pair, &100_000);
+        assert_eq!(client.get_fx_twap(&pair, &4_000), 1_250);
+
+        assert_eq!(
+            client.try_get_fx_twap(&pair, &4_001),
+            Err(Ok(Error::InsufficientHistory))
+        );
+    }
+
+    #[test]
+    fn test_observation_buffer_is_bounded() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let asset = Symbol::new(&env, "GOLD");
+        client.allow_feed(&reporter, &FeedKind::Asset, &asset);
+        client.set_observation_limit(&2);
+
+        for (timestamp, price) in [(100, 2_000), (200, 2_100), (300, 2_200)] {
+            env.ledger().with_mut(|li| li.timestamp = timestamp);
+            client.set_asset_price(&reporter, &asset, &price);
+        }
+
+        let observations = client.get_observations(&FeedKind::Asset, &asset);
+        assert_eq!(observations.len(), 2);
+        assert_eq!(observations.get_unchecked(0).timestamp, 200);
+        assert_eq!(observations.get_unchecked(1).cumulative, 2_000 * 100 + 2_100 * 100);
+        assert_eq!(client.get_asset_twap(&asset, &100), 2_100);
+    }
+
+    #[test]
+    fn test_sep40_interface() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let gold = Symbol::new(&env, "GOLD");
+        let gold_token = Address::generate(&env);
+        client.allow_feed(&reporter, &FeedKind::Asset, &gold);
+        client.set_asset_address(&gold_token, &gold);
+        client.set_price_config(&Asset::Other(Symbol::new(&env, "USD")), &7, &300);
+
+        env.ledger().with_mut(|li| li.timestamp = 600);
+        client.set_asset_price(&reporter, &gold, &20_000_000_000);
+        env.ledger().with_mut(|li| li.timestamp = 900);
+        client.set_asset_price(&reporter, &gold, &20_500_000_000);
+
+        assert_eq!(client.base(), Asset::Other(Symbol::new(&env, "USD")));
+        assert_eq!(client.decimals(), 7);
+        assert_eq!(client.resolution(), 300);
+        assert_eq!(client.assets(), Vec::from_array(&env, [Asset::Stellar(gold_token.clone())]));
+
+        let last = client.lastprice(&Asset::Stellar(gold_token.clone())).unwrap();
+        assert_eq!(last, PriceData { price: 20_500_000_000, timestamp: 900 });
+        assert_eq!(client.price(&Asset::Other(gold.clone()), &899).unwrap().price, 20_000_000_000);
+        assert_eq!(client.price(&Asset::Other(gold.clone()), &599), None);
+
+        let prices = client.prices(&Asset::Other(gold.clone()), &5).unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices.get_unchecked(0), last);
+
+        assert_eq!(client.lastprice(&Asset::Other(Symbol::new(&env, "SILVER"))), None);
+
+        // Prices of feeds registered with other decimals are rescaled to the SEP-40 decimals
+        client.register_feed(
+            &FeedKind::Asset,
+            &gold,
+            &FeedMetadata {
+                decimals: 9,
+                base: gold.clone(),
+                quote: Symbol::new(&env, "USD"),
+                unit: String::from_str(&env, "USD per troy ounce"),
+                source: String::from_str(&env, "LBMA"),
+                heartbeat: 300,
+            },
+        );
+        assert_eq!(client.lastprice(&Asset::Other(gold.clone())).unwrap().price, 205_000_000);
+        assert_eq!(client.price(&Asset::Other(gold.clone()), &899).unwrap().price, 200_000_000);
+        assert_eq!(client.prices(&Asset::Other(gold.clone()), &1).unwrap().get_unchecked(0).price, 205_000_000);
+
+        // Only freely readable assets are listed
+        client.pause_feed(&FeedKind::Asset, &gold);
+        assert!(client.assets().is_empty());
+        client.resume_feed(&FeedKind::Asset, &gold);
+        client.set_fee_config(&Address::generate(&env), &Address::generate(&env));
+        client.set_read_fee(&FeedKind::Asset, &gold, &10);
+        assert!(client.assets().is_empty());
+    }
+
+    #[test]
+    fn test_feed_metadata_registry() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let pair = Symbol::new(&env, "USDNGN");
+        client.allow_feed(&reporter, &FeedKind::Fx, &pair);
+        assert_eq!(
+            client.try_get_feed_metadata(&FeedKind::Fx, &pair),
+            Err(Ok(Error::FeedNotRegistered))
+        );
+
+        let metadata = FeedMetadata {
+            decimals: 2,
+            base: Symbol::new(&env, "USD"),
+            quote: Symbol::new(&env, "NGN"),
+            unit: String::from_str(&env, "NGN per USD"),
+            source: String::from_str(&env, "CBN official rate"),
+            heartbeat: 86_400,
+        };
+        client.register_feed(&FeedKind::Fx, &pair, &metadata);
+        assert_eq!(client.get_feed_metadata(&FeedKind::Fx, &pair), metadata);
+
+        client.set_fx(&reporter, &pair, &150_000);
+        assert_eq!(
+            client.try_set_fx(&reporter, &pair, &-1),
+            Err(Ok(Error::InvalidValue.into()))
+        );
+        assert_eq!(
+            client.try_set_fx(&reporter, &pair, &(MAX_WHOLE_UNITS * 100 + 1)),
+            Err(Ok(Error::InvalidValue.into()))
+        );
+    }
+
+    #[test]
+    fn test_circuit_breaker_holds_large_moves() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let country = Symbol::new(&env, "USA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &country);
+        client.set_max_deviation(&FeedKind::Cpi, &country, &1_000);
+
+        client.set_cpi(&reporter, &country, &300);
+        client.set_cpi(&reporter, &country, &320);
+        assert_eq!(client.get_cpi(&country), 320);
+
+        // A fat-fingered 10x update trips the breaker instead of publishing
+        client.set_cpi(&reporter, &country, &3_200);
+        assert_eq!(client.get_cpi(&country), 320);
+        assert!(client.is_tripped(&FeedKind::Cpi, &country));
+        assert_eq!(client.get_pending(&FeedKind::Cpi, &country).value, 3_200);
+        assert_eq!(
+            client.try_set_cpi(&reporter, &country, &330),
+            Err(Ok(Error::FeedTripped.into()))
+        );
+
+        client.reject_pending(&FeedKind::Cpi, &country);
+        assert!(!client.is_tripped(&FeedKind::Cpi, &country));
+        assert_eq!(client.get_cpi(&country), 320);
+
+        client.set_cpi(&reporter, &country, &400);
+        client.confirm_pending(&FeedKind::Cpi, &country);
+        assert_eq!(client.get_cpi(&country), 400);
+        assert_eq!(
+            client.try_get_pending(&FeedKind::Cpi, &country),
+            Err(Ok(Error::NoPendingValue))
+        );
+
+        // Pending values are not published into paused or retired feeds
+        client.set_cpi(&reporter, &country, &4_000);
+        client.pause_feed(&FeedKind::Cpi, &country);
+        assert_eq!(
+            client.try_confirm_pending(&FeedKind::Cpi, &country),
+            Err(Ok(Error::FeedPaused.into()))
+        );
+        assert!(client.is_tripped(&FeedKind::Cpi, &country));
+
+        client.retire_feed(&FeedKind::Cpi, &country);
+        client.confirm_pending(&FeedKind::Cpi, &country);
+        assert!(!client.is_tripped(&FeedKind::Cpi, &country));
+        let published = env.as_contract(&client.address, || storage::read_value(&env, FeedKind::Cpi, &country));
+        assert_eq!(published.unwrap().value, 400);
+    }
+
+    fn sign_report(
+        env: &Env,
+        client: &OracleContractClient,
+        key: &SigningKey,
+        report: (FeedKind, Symbol, i128, u64, u64),
+    ) -> BytesN<64> {
+        let (kind, feed, value, timestamp, nonce) = report;
+        let message = (client.address.clone(), kind, feed, value, timestamp, nonce).to_xdr(env);
+
+        let mut bytes = std::vec::Vec::new();
+        for byte in message.iter() {
+            bytes.push(byte);
+        }
+
+        BytesN::from_array(env, &key.sign(&bytes).to_bytes())
+    }
+
+    #[test]
+    fn test_signed_reports_relayed_by_anyone() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let pair = Symbol::new(&env, "USDNGN");
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = BytesN::from_array(&env, &key.verifying_key().to_bytes());
+        client.allow_feed(&reporter, &FeedKind::Fx, &pair);
+        client.set_reporter_key(&reporter, &public_key);
+        assert_eq!(client.get_key_reporter(&public_key), reporter);
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        let report = (FeedKind::Fx, pair.clone(), 1_450, 990, 1);
+        let signature = sign_report(&env, &client, &key, report);
+        client.submit_signed(&public_key, &FeedKind::Fx, &pair, &1_450, &990, &1, &signature);
+
+        assert_eq!(client.get_fx(&pair), 1_450);
+        assert_eq!(client.get_nonce(&public_key), 1);
+
+        // The value is dated by the signed report, not the ledger it was relayed in
+        assert_eq!(client.get_with_confidence(&FeedKind::Fx, &pair).2, 990);
+
+        // Replaying the same report is rejected
+        assert_eq!(
+            client.try_submit_signed(&public_key, &FeedKind::Fx, &pair, &1_450, &990, &1, &signature),
+            Err(Ok(Error::InvalidNonce.into()))
+        );
+
+        // A tampered value fails signature verification
+        let signature = sign_report(&env, &client, &key, (FeedKind::Fx, pair.clone(), 1_460, 995, 2));
+        assert!(client
+            .try_submit_signed(&public_key, &FeedKind::Fx, &pair, &9_999, &995, &2, &signature)
+            .is_err());
+        assert_eq!(client.get_fx(&pair), 1_450);
+    }
+
+    #[test]
+    fn test_signed_report_rejects_unknown_key_and_old_reports() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let pair = Symbol::new(&env, "USDNGN");
+        let key = SigningKey::from_bytes(&[9; 32]);
+        let public_key = BytesN::from_array(&env, &key.verifying_key().to_bytes());
+        client.allow_feed(&reporter, &FeedKind::Fx, &pair);
+
+        env.ledger().with_mut(|li| li.timestamp = 10_000);
+        let signature = sign_report(&env, &client, &key, (FeedKind::Fx, pair.clone(), 1_450, 9_000, 1));
+        assert_eq!(
+            client.try_submit_signed(&public_key, &FeedKind::Fx, &pair, &1_450, &9_000, &1, &signature),
+            Err(Ok(Error::UnknownKey.into()))
+        );
+
+        client.set_reporter_key(&reporter, &public_key);
+        assert_eq!(
+            client.try_submit_signed(&public_key, &FeedKind::Fx, &pair, &1_450, &9_000, &1, &signature),
+            Err(Ok(Error::InvalidTimestamp.into()))
+        );
+
+        client.remove_reporter(&reporter);
+        assert_eq!(client.try_get_key_reporter(&public_key), Err(Ok(Error::UnknownKey)));
+    }
+
+    #[test]
+    fn test_batch_set_and_get() {
+        let env = Env::default();
+        let (client, _, reporter) = setup(&env);
+
+        let usa = Symbol::new(&env, "USA");
+        let pair = Symbol::new(&env, "USDNGN");
+        let gold = Symbol::new(&env, "GOLD");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &usa);
+        client.allow_feed(&reporter, &FeedKind::Fx, &pair);
+        client.allow_feed(&reporter, &FeedKind::Asset, &gold);
+
+        client.set_many(
+            &reporter,
+            &Vec::from_array(
+                &env,
+                [
+                    (FeedKind::Cpi, usa.clone(), 310),
+                    (FeedKind::Fx, pair.clone(), 1_500),
+                    (FeedKind::Asset, gold.clone(), 2_300),
+                ],
+            ),
+        );
+
+        let values = client.get_many(&Vec::from_array(
+            &env,
+            [
+                (FeedKind::Cpi, usa),
+                (FeedKind::Fx, pair),
+                (FeedKind::Asset, Symbol::new(&env, "SILVER")),
+            ],
+        ));
+        assert_eq!(values.len(), 3);
+        assert_eq!(values.get_unchecked(0).unwrap().value, 310);
+        assert_eq!(values.get_unchecked(1).unwrap().value, 1_500);
+        assert_eq!(values.get_unchecked(2), None);
+
+        // One unauthorized entry rejects the whole batch
+        assert_eq!(
+            client.try_set_many(
+                &reporter,
+                &Vec::from_array(
+                    &env,
+                    [
+                        (FeedKind::Asset, gold.clone(), 2_400),
+                        (FeedKind::Cpi, Symbol::new(&env, "NGA"), 500),
+                    ],
+                ),
+            ),
+            Err(Ok(Error::Unauthorized.into()))
+        );
+        assert_eq!(client.get_asset_price(&gold), 2_300);
+    }
+
+    #[test]
+    fn test_optimistic_cpi() {
+        let env = Env::default();
+        let (client, admin, _reporter) = setup(&env);
+
+        let token_id = env.register_stellar_asset_contract(admin.clone());
+        let token = token::Client::new(&env, &token_id);
+        let token_admin = token::StellarAssetClient::new(&env, &token_id);
+        let proposer = Address::generate(&env);
+        let challenger = Address::generate(&env);
+        let arbiter = Address::generate(&env);
+        token_admin.mint(&proposer, &1_000);
+        token_admin.mint(&challenger, &1_000);
+
+        let usa = Symbol::new(&env, "USA");
+        assert_eq!(
+            client.try_propose_cpi(&proposer, &usa, &310),
+            Err(Ok(Error::OptimisticNotConfigured.into()))
+        );
+
+        client.set_optimistic_config(&token_id, &100, &3_600, &arbiter);
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+        // Undisputed proposals publish once the window passes
+        client.propose_cpi(&proposer, &usa, &310);
+        assert_eq!(token.balance(&proposer), 900);
+        assert_eq!(
+            client.try_propose_cpi(&challenger, &usa, &320),
+            Err(Ok(Error::ProposalExists.into()))
+        );
+        assert_eq!(
+            client.try_settle_cpi(&usa),
+            Err(Ok(Error::DisputeWindowOpen.into()))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp = 4_600);
+        assert_eq!(
+            client.try_dispute_cpi(&challenger, &usa),
+            Err(Ok(Error::DisputeWindowClosed.into()))
+        );
+        client.settle_cpi(&usa);
+        assert_eq!(client.get_cpi(&usa), 310);
+        assert_eq!(token.balance(&proposer), 1_000);
+        assert_eq!(client.try_get_proposal(&usa), Err(Ok(Error::ProposalNotFound)));
+
+        // Disputed proposals wait for the arbiter, who awards both bonds
+        client.propose_cpi(&proposer, &usa, &900);
+        client.dispute_cpi(&challenger, &usa);
+        assert_eq!(token.balance(&challenger), 900);
+        assert!(client.get_proposal(&usa).disputed);
+        assert_eq!(client.get_challenger(&usa), challenger);
+
+        env.ledger().with_mut(|li| li.timestamp = 9_000);
+        assert_eq!(
+            client.try_settle_cpi(&usa),
+            Err(Ok(Error::ProposalDisputed.into()))
+        );
+
+        client.resolve_dispute(&usa, &false);
+        assert_eq!(client.get_cpi(&usa), 310);
+        assert_eq!(token.balance(&challenger), 1_100);
+        assert_eq!(token.balance(&proposer), 900);
+        assert_eq!(
+            client.try_resolve_dispute(&usa, &true),
+            Err(Ok(Error::ProposalNotFound.into()))
+        );
+
+        // Settled proposals go through the circuit breaker
+        client.set_max_deviation(&FeedKind::Cpi, &usa, &1_000);
+        client.propose_cpi(&proposer, &usa, &900);
+        env.ledger().with_mut(|li| li.timestamp = 13_000);
+        client.settle_cpi(&usa);
+        assert_eq!(client.get_cpi(&usa), 310);
+        assert_eq!(client.get_pending(&FeedKind::Cpi, &usa).value, 900);
+        client.propose_cpi(&proposer, &usa, &320);
+        env.ledger().with_mut(|li| li.timestamp = 17_000);
+        assert_eq!(
+            client.try_settle_cpi(&usa),
+            Err(Ok(Error::FeedTripped.into()))
+        );
+        client.reject_pending(&FeedKind::Cpi, &usa);
+
+        // and cannot publish to a paused feed
+        client.pause_feed(&FeedKind::Cpi, &usa);
+        assert_eq!(
+            client.try_settle_cpi(&usa),
+            Err(Ok(Error::FeedPaused.into()))
+        );
+        client.resume_feed(&FeedKind::Cpi, &usa);
+        client.settle_cpi(&usa);
+        assert_eq!(client.get_cpi(&usa), 320);
+
+        // Proposals on a feed retired during their window refund the bonds without publishing
+        client.propose_cpi(&proposer, &usa, &330);
+        client.dispute_cpi(&challenger, &usa);
+        client.retire_feed(&FeedKind::Cpi, &usa);
+        client.resolve_dispute(&usa, &true);
+        assert_eq!(token.balance(&proposer), 1_000);
+        assert_eq!(client.try_get_proposal(&usa), Err(Ok(Error::ProposalNotFound)));
+        let published = env.as_contract(&client.address, || storage::read_value(&env, FeedKind::Cpi, &usa));
+        assert_eq!(published.unwrap().value, 320);
+
+        // A dispute window is required for proposals to be challengeable
+        assert_eq!(
+            client.try_set_optimistic_config(&token_id, &100, &0, &arbiter),
+            Err(Ok(Error::InvalidWindow.into()))
+        );
+    }
+
+    #[test]
+    fn test_reporter_staking_and_slashing() {
+        let env = Env::default();
+        let (client, admin, _reporter) = setup(&env);
+
+        let token_id = env.register_stellar_asset_contract(admin.clone());
+        let token = token::Client::new(&env, &token_id);
+        let token_admin = token::StellarAssetClient::new(&env, &token_id);
+
+        let usa = Symbol::new(&env, "USA");
+        client.set_quorum(&3);
+        let reporters = [
+            add_cpi_reporter(&env, &client, &usa),
+            add_cpi_reporter(&env, &client, &usa),
+            add_cpi_reporter(&env, &client, &usa),
+        ];
+        for reporter in reporters.iter() {
+            token_admin.mint(reporter, &1_000);
+        }
+
+        // Outliers more than 5% from the median lose 200
+        client.set_stake_config(&token_id, &500, &500, &200, &86_400);
+        assert_eq!(
+            client.try_set_cpi(&reporters[0], &usa, &310),
+            Err(Ok(Error::InsufficientStake.into()))
+        );
+
+        for reporter in reporters.iter() {
+            client.stake(reporter, &600);
+        }
+        assert_eq!(token.balance(&reporters[0]), 400);
+
+        client.set_cpi(&reporters[0], &usa, &310);
+        client.set_cpi(&reporters[1], &usa, &312);
+        client.set_cpi(&reporters[2], &usa, &400);
+        assert_eq!(client.get_cpi(&usa), 312);
+        assert_eq!(client.get_stake(&reporters[0]), 600);
+        assert_eq!(client.get_stake(&reporters[2]), 400);
+        assert_eq!(token.balance(&admin), 200);
+
+        // The slashed reporter is below the minimum until it tops up
+        assert_eq!(
+            client.try_set_cpi(&reporters[2], &usa, &312),
+            Err(Ok(Error::InsufficientStake.into()))
+        );
+
+        assert_eq!(client.slash(&reporters[1], &1_000), 600);
+        assert_eq!(client.get_stake(&reporters[1]), 0);
+        assert_eq!(token.balance(&admin), 800);
+
+        // An outlier cannot escape its slash by unstaking before the round closes
+        token_admin.mint(&reporters[1], &200);
+        client.stake(&reporters[1], &600);
+        client.stake(&reporters[2], &200);
+        client.set_cpi(&reporters[2], &usa, &500);
+        client.unstake(&reporters[2], &600);
+        assert_eq!(client.get_stake(&reporters[2]), 0);
+        assert_eq!(token.balance(&reporters[2]), 200);
+        client.set_cpi(&reporters[0], &usa, &310);
+        client.set_cpi(&reporters[1], &usa, &312);
+        assert_eq!(client.get_unbonding(&reporters[2]).amount, 400);
+        assert_eq!(token.balance(&admin), 1_000);
+
+        // Unstaked tokens are only paid out once the unbonding period has passed
+        client.unstake(&reporters[0], &600);
+        assert_eq!(
+            client.try_unstake(&reporters[0], &1),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+        assert_eq!(
+            client.try_withdraw_unbonded(&reporters[0]),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+        env.ledger().with_mut(|li| li.timestamp += 86_400);
+        assert_eq!(client.withdraw_unbonded(&reporters[0]), 600);
+        assert_eq!(token.balance(&reporters[0]), 1_000);
+        assert_eq!(client.get_unbonding(&reporters[0]).amount, 0);
+    }
+
+    #[test]
+    fn test_fx_cross_rates() {
+        let env = Env::default();
+        let (client, _admin, reporter) = setup(&env);
+
+        let usd = Symbol::new(&env, "USD");
+        let eur = Symbol::new(&env, "EUR");
+        let ngn = Symbol::new(&env, "NGN");
+        let kes = Symbol::new(&env, "KES");
+        assert_eq!(
+            client.try_get_cross_rate(&eur, &ngn),
+            Err(Ok(Error::FxBaseNotSet))
+        );
+
+        // Rates have 7 decimals: 1 EUR = 1.08 USD, 1 USD = 1,500 NGN
+        client.set_fx_base(&usd, &7);
+        for pair in ["EURUSD", "USDNGN"] {
+            client.allow_feed(&reporter, &FeedKind::Fx, &Symbol::new(&env, pair));
+        }
+        client.set_fx(&reporter, &Symbol::new(&env, "EURUSD"), &10_800_000);
+        client.set_fx(&reporter, &Symbol::new(&env, "USDNGN"), &15_000_000_000);
+
+        assert_eq!(client.get_cross_rate(&eur, &usd), 10_800_000);
+        assert_eq!(client.get_cross_rate(&usd, &eur), 9_259_259);
+        assert_eq!(client.get_cross_rate(&eur, &ngn), 16_200_000_000);
+        assert_eq!(client.get_cross_rate(&ngn, &eur), 6_173);
+        assert_eq!(client.get_cross_rate(&ngn, &ngn), 10_000_000);
+        assert_eq!(
+            client.try_get_cross_rate(&eur, &kes),
+            Err(Ok(Error::NoFxPath))
+        );
+
+        // Triangulated rates are as old as their oldest pair
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_fx(&reporter, &Symbol::new(&env, "USDNGN"), &15_000_000_000);
+        assert_eq!(client.get_cross_rate_with_timestamp(&eur, &ngn).timestamp, 0);
+        env.ledger().with_mut(|li| li.timestamp = 1_500);
+        assert_eq!(
+            client.try_get_cross_rate_fresh(&eur, &ngn, &1_000),
+            Err(Ok(Error::Stale))
+        );
+        assert_eq!(client.get_cross_rate_fresh(&usd, &ngn, &1_000), 15_000_000_000);
+
+        // Pairs registered with other decimals are converted to the FX decimals
+        let usdngn = Symbol::new(&env, "USDNGN");
+        client.register_feed(
+            &FeedKind::Fx,
+            &usdngn,
+            &FeedMetadata {
+                decimals: 4,
+                base: usd.clone(),
+                quote: ngn.clone(),
+                unit: String::from_str(&env, "NGN per USD"),
+                source: String::from_str(&env, "CBN"),
+                heartbeat: 0,
+            },
+        );
+        client.set_fx(&reporter, &usdngn, &15_000_000);
+        assert_eq!(client.get_cross_rate(&usd, &ngn), 15_000_000_000);
+        assert_eq!(client.get_cross_rate(&ngn, &usd), 6_667);
+        assert_eq!(client.get_cross_rate(&eur, &ngn), 16_200_000_000);
+
+        // A paused pair is not routed around
+        client.pause_feed(&FeedKind::Fx, &Symbol::new(&env, "EURUSD"));
+        assert_eq!(
+            client.try_get_cross_rate(&eur, &ngn),
+            Err(Ok(Error::FeedPaused))
+        );
+    }
+
+    #[contract]
+    struct Consumer;
+
+    #[contractimpl]
+    impl Consumer {
+        pub fn on_oracle_update(env: Env, feed: Symbol, value: i128) {
+            let count: u32 = env.storage().instance().get(&Symbol::new(&env, "count")).unwrap_or(0);
+            env.storage().instance().set(&Symbol::new(&env, "count"), &(count + 1));
+            env.storage().instance().set(&feed, &value);
+        }
+
+        pub fn count(env: Env) -> u32 {
+            env.storage().instance().get(&Symbol::new(&env, "count")).unwrap_or(0)
+        }
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let env = Env::default();
+        let (client, admin, reporter) = setup(&env);
+
+        let usa = Symbol::new(&env, "USA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &usa);
+
+        let any = ConsumerClient::new(&env, &env.register_contract(None, Consumer));
+        let threshold = ConsumerClient::new(&env, &env.register_contract(None, Consumer));
+        let deviation = ConsumerClient::new(&env, &env.register_contract(None, Consumer));
+        client.subscribe(&any.address, &FeedKind::Cpi, &usa, &Trigger::Any);
+        // Subscribers are added by the admin, not by the consumer
+        assert_eq!(env.auths()[0].0, admin);
+        client.subscribe(&threshold.address, &FeedKind::Cpi, &usa, &Trigger::Threshold(320));
+        client.subscribe(&deviation.address, &FeedKind::Cpi, &usa, &Trigger::Deviation(500));
+
+        // A subscriber without the callback does not block updates
+        let broken = Address::generate(&env);
+        client.subscribe(&broken, &FeedKind::Cpi, &usa, &Trigger::Any);
+
+        for cpi in [300, 310, 330, 340] {
+            client.set_cpi(&reporter, &usa, &cpi);
+        }
+        assert_eq!(client.get_cpi(&usa), 340);
+
+        assert_eq!(any.count(), 4);
+        // Crossed 320 between 310 and 330
+        assert_eq!(threshold.count(), 1);
+        // 300, then 330 (10% from 300); 310 and 340 are within 5% of the last notification
+        assert_eq!(deviation.count(), 2);
+
+        client.unsubscribe(&any.address, &FeedKind::Cpi, &usa);
+        assert_eq!(client.get_subscriptions(&FeedKind::Cpi, &usa).len(), 3);
+        assert_eq!(
+            client.try_unsubscribe(&any.address, &FeedKind::Cpi, &usa),
+            Err(Ok(Error::SubscriptionNotFound.into()))
+        );
+    }
+
+    #[test]
+    fn test_fallback_oracles() {
+        let env = Env::default();
+        let (client, _admin, reporter) = setup(&env);
+
+        let (aureus, _, aureus_reporter) = setup(&env);
+        let (sep40, _, sep40_reporter) = setup(&env);
+        let gold = Symbol::new(&env, "GOLD");
+        aureus.allow_feed(&aureus_reporter, &FeedKind::Asset, &gold);
+        sep40.allow_feed(&sep40_reporter, &FeedKind::Asset, &gold);
+        sep40.set_price_config(&Asset::Other(Symbol::new(&env, "USD")), &4, &60);
+        client.set_price_config(&Asset::Other(Symbol::new(&env, "USD")), &7, &60);
+        client.allow_feed(&reporter, &FeedKind::Asset, &gold);
+
+        client.set_fallbacks(&Vec::from_array(
+            &env,
+            [
+                Fallback::Aureus(aureus.address.clone()),
+                Fallback::Sep40(sep40.address.clone()),
+            ],
+        ));
+        assert_eq!(
+            client.try_get_with_fallback(&FeedKind::Asset, &gold, &600),
+            Err(Ok(Error::FeedNotFound))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_asset_price(&reporter, &gold, &23_000_000_000);
+        aureus.set_asset_price(&aureus_reporter, &gold, &23_100_000_000);
+        sep40.set_asset_price(&sep40_reporter, &gold, &23_200_000);
+
+        let value = client.get_with_fallback(&FeedKind::Asset, &gold, &600);
+        assert_eq!(value.value, 23_000_000_000);
+        assert_eq!(value.source, client.address);
+
+        // The local feed goes stale, so the first fallback with a fresh value answers
+        env.ledger().with_mut(|li| li.timestamp = 2_000);
+        aureus.set_asset_price(&aureus_reporter, &gold, &23_300_000_000);
+        let value = client.get_with_fallback(&FeedKind::Asset, &gold, &600);
+        assert_eq!(value.value, 23_300_000_000);
+        assert_eq!(value.source, aureus.address);
+
+        // SEP-40 prices are rescaled from 4 to 7 decimals
+        env.ledger().with_mut(|li| li.timestamp = 3_000);
+        sep40.set_asset_price(&sep40_reporter, &gold, &23_400_000);
+        let value = client.get_with_fallback(&FeedKind::Asset, &gold, &600);
+        assert_eq!(value.value, 23_400_000_000);
+        assert_eq!(value.source, sep40.address);
+        assert_eq!(value.timestamp, 3_000);
+
+        env.ledger().with_mut(|li| li.timestamp = 9_000);
+        assert_eq!(
+            client.try_get_with_fallback(&FeedKind::Asset, &gold, &600),
+            Err(Ok(Error::Stale))
+        );
+
+        // Fallback values are converted to the local feed's registered decimals
+        let metadata = |decimals: u32| FeedMetadata {
+            decimals,
+            base: gold.clone(),
+            quote: Symbol::new(&env, "USD"),
+            unit: String::from_str(&env, "USD per troy ounce"),
+            source: String::from_str(&env, "LBMA"),
+            heartbeat: 0,
+        };
+        aureus.register_feed(&FeedKind::Asset, &gold, &metadata(9));
+        client.register_feed(&FeedKind::Asset, &gold, &metadata(7));
+        aureus.set_asset_price(&aureus_reporter, &gold, &2_350_000_000_000);
+        let value = client.get_with_fallback(&FeedKind::Asset, &gold, &600);
+        assert_eq!(value.value, 23_500_000_000);
+        assert_eq!(value.source, aureus.address);
+
+        // A paused local feed is not served from fallbacks
+        client.pause_feed(&FeedKind::Asset, &gold);
+        assert_eq!(
+            client.try_get_with_fallback(&FeedKind::Asset, &gold, &600),
+            Err(Ok(Error::FeedPaused))
+        );
+    }
+
+    #[test]
+    fn test_confidence() {
+        let env = Env::default();
+        let (client, _admin, _reporter) = setup(&env);
+
+        let usa = Symbol::new(&env, "USA");
+        let reporters = [
+            add_cpi_reporter(&env, &client, &usa),
+            add_cpi_reporter(&env, &client, &usa),
+            add_cpi_reporter(&env, &client, &usa),
+        ];
+
+        // A single reporter with no stated uncertainty gives zero confidence
+        client.set_cpi(&reporters[0], &usa, &300);
+        assert_eq!(client.get_with_confidence(&FeedKind::Cpi, &usa), (300, 0, 0));
+
+        // Dispersion: deviations from the median 310 are 10, 0 and 30
+        client.set_quorum(&3);
+        client.set_cpi(&reporters[0], &usa, &300);
+        client.set_cpi(&reporters[1], &usa, &310);
+        client.set_cpi(&reporters[2], &usa, &340);
+        assert_eq!(client.get_with_confidence(&FeedKind::Cpi, &usa), (310, 10, 0));
+
+        // Stated uncertainty wider than the dispersion wins
+        client.submit_with_confidence(&reporters[0], &FeedKind::Cpi, &usa, &311, &25);
+        client.submit_with_confidence(&reporters[1], &FeedKind::Cpi, &usa, &312, &20);
+        client.set_cpi(&reporters[2], &usa, &313);
+        assert_eq!(client.get_with_confidence(&FeedKind::Cpi, &usa), (312, 20, 0));
+
+        assert_eq!(
+            client.try_submit_with_confidence(&reporters[0], &FeedKind::Cpi, &usa, &311, &-1),
+            Err(Ok(Error::InvalidValue.into()))
+        );
+    }
+
+    #[test]
+    fn test_commit_reveal() {
+        let env = Env::default();
+        let (client, admin, _reporter) = setup(&env);
+
+        let token_id = env.register_stellar_asset_contract(admin.clone());
+        let token_admin = token::StellarAssetClient::new(&env, &token_id);
+
+        let usa = Symbol::new(&env, "USA");
+        let reporters = [
+            add_cpi_reporter(&env, &client, &usa),
+            add_cpi_reporter(&env, &client, &usa),
+            add_cpi_reporter(&env, &client, &usa),
+        ];
+        // Penalties need stake to take
+        assert_eq!(
+            client.try_set_commit_reveal(&600, &600, &250),
+            Err(Ok(Error::StakeNotConfigured.into()))
+        );
+
+        client.set_stake_config(&token_id, &0, &0, &0, &86_400);
+        for reporter in reporters.iter() {
+            token_admin.mint(reporter, &1_000);
+            client.stake(reporter, &1_000);
+        }
+        client.set_quorum(&2);
+        client.set_commit_reveal(&600, &600, &250);
+
+        assert_eq!(
+            client.try_set_cpi(&reporters[0], &usa, &310),
+            Err(Ok(Error::CommitRevealRequired.into()))
+        );
+
+        let salt = BytesN::from_array(&env, &[7; 32]);
+        let commitment = |reporter: &Address, round: u32, value: i128| -> BytesN<32> {
+            let message = (reporter.clone(), FeedKind::Cpi, usa.clone(), round, value, salt.clone());
+            BytesN::from_array(&env, &env.crypto().sha256(&message.to_xdr(&env)).to_array())
+        };
+
+        // The third reporter copies the second one's public commitment
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.commit(&reporters[0], &FeedKind::Cpi, &usa, &commitment(&reporters[0], 0, 310));
+        client.commit(&reporters[1], &FeedKind::Cpi, &usa, &commitment(&reporters[1], 0, 312));
+        let copied = client.get_commit_round(&FeedKind::Cpi, &usa).commits.get_unchecked(reporters[1].clone());
+        client.commit(&reporters[2], &FeedKind::Cpi, &usa, &copied);
+        assert_eq!(
+            client.try_reveal(&reporters[0], &FeedKind::Cpi, &usa, &310, &salt),
+            Err(Ok(Error::NotRevealPhase.into()))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp = 1_600);
+        assert_eq!(
+            client.try_commit(&reporters[0], &FeedKind::Cpi, &usa, &commitment(&reporters[0], 0, 311)),
+            Err(Ok(Error::CommitPhaseClosed.into()))
+        );
+        assert_eq!(
+            client.try_reveal(&reporters[0], &FeedKind::Cpi, &usa, &311, &salt),
+            Err(Ok(Error::InvalidReveal.into()))
+        );
+        client.reveal(&reporters[0], &FeedKind::Cpi, &usa, &310, &salt);
+        client.reveal(&reporters[1], &FeedKind::Cpi, &usa, &312, &salt);
+
+        // Replaying the revealed value and salt does not open the copied commitment
+        assert_eq!(
+            client.try_reveal(&reporters[2], &FeedKind::Cpi, &usa, &312, &salt),
+            Err(Ok(Error::InvalidReveal.into()))
+        );
+        assert_eq!(
+            client.try_finalize_round(&FeedKind::Cpi, &usa),
+            Err(Ok(Error::RevealPhaseOpen.into()))
+        );
+
+        // The copier never reveals and is penalized
+        env.ledger().with_mut(|li| li.timestamp = 2_200);
+        client.finalize_round(&FeedKind::Cpi, &usa);
+        assert_eq!(client.get_cpi(&usa), 311);
+        assert_eq!(client.get_stake(&reporters[2]), 750);
+        assert_eq!(client.get_stake(&reporters[0]), 1_000);
+        assert_eq!(client.get_round(&FeedKind::Cpi, &usa).id, 1);
+        assert_eq!(
+            client.try_get_commit_round(&FeedKind::Cpi, &usa),
+            Err(Ok(Error::NoCommitRound))
+        );
+
+        // A round cannot finish into a paused feed
+        env.ledger().with_mut(|li| li.timestamp = 3_000);
+        client.commit(&reporters[0], &FeedKind::Cpi, &usa, &commitment(&reporters[0], 1, 320));
+        client.commit(&reporters[1], &FeedKind::Cpi, &usa, &commitment(&reporters[1], 1, 322));
+        client.pause_feed(&FeedKind::Cpi, &usa);
+        env.ledger().with_mut(|li| li.timestamp = 3_600);
+        client.reveal(&reporters[0], &FeedKind::Cpi, &usa, &320, &salt);
+        client.reveal(&reporters[1], &FeedKind::Cpi, &usa, &322, &salt);
+        env.ledger().with_mut(|li| li.timestamp = 4_200);
+        assert_eq!(
+            client.try_finalize_round(&FeedKind::Cpi, &usa),
+            Err(Ok(Error::FeedPaused.into()))
+        );
+        client.resume_feed(&FeedKind::Cpi, &usa);
+
+        // nor past a value held by the circuit breaker
+        let proposer = Address::generate(&env);
+        token_admin.mint(&proposer, &100);
+        client.set_optimistic_config(&token_id, &100, &60, &admin);
+        client.set_max_deviation(&FeedKind::Cpi, &usa, &1_000);
+        client.propose_cpi(&proposer, &usa, &900);
+        env.ledger().with_mut(|li| li.timestamp = 4_300);
+        client.settle_cpi(&usa);
+        assert_eq!(
+            client.try_finalize_round(&FeedKind::Cpi, &usa),
+            Err(Ok(Error::FeedTripped.into()))
+        );
+        assert_eq!(
+            client.try_commit(&reporters[2], &FeedKind::Cpi, &usa, &commitment(&reporters[2], 1, 321)),
+            Err(Ok(Error::FeedTripped.into()))
+        );
+
+        client.reject_pending(&FeedKind::Cpi, &usa);
+        client.finalize_round(&FeedKind::Cpi, &usa);
+        assert_eq!(client.get_cpi(&usa), 321);
+    }
+
+    #[test]
+    fn test_feed_lifecycle() {
+        let env = Env::default();
+        let (client, _admin, reporter) = setup(&env);
+
+        let nga = Symbol::new(&env, "NGA");
+        let nga2 = Symbol::new(&env, "NGA2");
+        let usa = Symbol::new(&env, "USA");
+        for country in [&nga, &nga2, &usa] {
+            client.allow_feed(&reporter, &FeedKind::Cpi, country);
+            client.set_cpi(&reporter, country, &500);
+        }
+
+        // Pausing one country leaves the others serving
+        client.pause_feed(&FeedKind::Cpi, &nga);
+        assert_eq!(client.get_feed_status(&FeedKind::Cpi, &nga), FeedStatus::Paused);
+        assert_eq!(client.try_get_cpi(&nga), Err(Ok(Error::FeedPaused)));
+        assert_eq!(
+            client.try_set_cpi(&reporter, &nga, &510),
+            Err(Ok(Error::FeedPaused.into()))
+        );
+        assert_eq!(client.get_cpi(&usa), 500);
+
+        // Deprecated feeds keep serving and name their successor
+        client.deprecate_feed(&FeedKind::Cpi, &nga, &nga2);
+        assert_eq!(client.get_cpi(&nga), 500);
+        assert_eq!(client.get_successor(&FeedKind::Cpi, &nga), Some(nga2.clone()));
+
+        client.retire_feed(&FeedKind::Cpi, &nga);
+        assert_eq!(client.try_get_cpi(&nga), Err(Ok(Error::FeedRetired)));
+        assert_eq!(
+            client.try_get_cpi_with_timestamp(&nga),
+            Err(Ok(Error::FeedRetired))
+        );
+        assert_eq!(client.get_successor(&FeedKind::Cpi, &nga), Some(nga2));
+        assert_eq!(
+            client.try_resume_feed(&FeedKind::Cpi, &nga),
+            Err(Ok(Error::FeedRetired.into()))
+        );
+    }
+
+    #[test]
+    fn test_premium_read_fees() {
+        let env = Env::default();
+        let (client, admin, reporter) = setup(&env);
+
+        let token_id = env.register_stellar_asset_contract(admin.clone());
+        let token = token::Client::new(&env, &token_id);
+        let token_admin = token::StellarAssetClient::new(&env, &token_id);
+        let treasury = Address::generate(&env);
+        let consumer = Address::generate(&env);
+        let savings = Address::generate(&env);
+        token_admin.mint(&consumer, &1_000);
+
+        let usa = Symbol::new(&env, "USA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &usa);
+        client.set_cpi(&reporter, &usa, &310);
+
+        assert_eq!(
+            client.try_set_read_fee(&FeedKind::Cpi, &usa, &10),
+            Err(Ok(Error::NotInitialized.into()))
+        );
+        client.set_fee_config(&token_id, &treasury);
+        client.set_read_fee(&FeedKind::Cpi, &usa, &10);
+        client.set_free_reader(&savings, &true);
+
+        // Premium feeds are only served through paid reads
+        assert_eq!(client.try_get_cpi(&usa), Err(Ok(Error::Unauthorized)));
+        assert_eq!(client.try_get_cpi_at(&usa, &202401), Err(Ok(Error::Unauthorized)));
+        assert_eq!(client.try_get_cpi_range(&usa, &0, &u32::MAX), Err(Ok(Error::Unauthorized)));
+        assert_eq!(client.try_get_round(&FeedKind::Cpi, &usa), Err(Ok(Error::Unauthorized)));
+        assert_eq!(client.try_get_subscriptions(&FeedKind::Cpi, &usa), Err(Ok(Error::Unauthorized)));
+        assert_eq!(client.try_get_pending(&FeedKind::Cpi, &usa), Err(Ok(Error::Unauthorized)));
+        assert_eq!(
+            client.try_get_observations(&FeedKind::Cpi, &usa),
+            Err(Ok(Error::Unauthorized))
+        );
+
+        // and only whitelisted subscribers are notified of their updates
+        let paying = ConsumerClient::new(&env, &env.register_contract(None, Consumer));
+        let whitelisted = ConsumerClient::new(&env, &env.register_contract(None, Consumer));
+        client.set_free_reader(&whitelisted.address, &true);
+        client.subscribe(&paying.address, &FeedKind::Cpi, &usa, &Trigger::Any);
+        client.subscribe(&whitelisted.address, &FeedKind::Cpi, &usa, &Trigger::Any);
+        client.set_cpi(&reporter, &usa, &310);
+        assert_eq!(paying.count(), 0);
+        assert_eq!(whitelisted.count(), 1);
+
+        // Prepaid credit is used first, then the consumer pays per read
+        client.deposit_credit(&consumer, &consumer, &15);
+        assert_eq!(client.read_premium(&consumer, &FeedKind::Cpi, &usa).value, 310);
+        assert_eq!(client.get_credit(&consumer), 5);
+        client.read_premium(&consumer, &FeedKind::Cpi, &usa);
+        assert_eq!(client.get_credit(&consumer), 5);
+        assert_eq!(token.balance(&consumer), 975);
+
+        client.read_premium(&savings, &FeedKind::Cpi, &usa);
+        assert_eq!(client.get_fee_balance(), 20);
+
+        client.withdraw_fees(&20);
+        assert_eq!(token.balance(&treasury), 20);
+        assert_eq!(client.get_fee_balance(), 0);
+        assert_eq!(
+            client.try_withdraw_fees(&1),
+            Err(Ok(Error::InvalidAmount.into()))
+        );
+
+        client.set_read_fee(&FeedKind::Cpi, &usa, &0);
+        assert_eq!(client.get_cpi(&usa), 310);
+    }
+
+    #[test]
+    fn test_feed_health() {
+        let env = Env::default();
+        let (client, _admin, _reporter) = setup(&env);
+
+        let usa = Symbol::new(&env, "USA");
+        let nga = Symbol::new(&env, "NGA");
+        let reporters = [
+            add_cpi_reporter(&env, &client, &usa),
+            add_cpi_reporter(&env, &client, &usa),
+        ];
+        client.allow_feed(&reporters[0], &FeedKind::Cpi, &nga);
+        client.register_feed(
+            &FeedKind::Cpi,
+            &usa,
+            &FeedMetadata {
+                decimals: 2,
+                base: usa.clone(),
+                quote: Symbol::new(&env, "USD"),
+                unit: String::from_str(&env, "CPI index, 1982-84=100"),
+                source: String::from_str(&env, "BLS"),
+                heartbeat: 3_600,
+            },
+        );
+
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_quorum(&2);
+        client.set_cpi(&reporters[0], &usa, &31_000);
+        client.set_cpi(&reporters[1], &usa, &31_200);
+        client.set_cpi(&reporters[0], &usa, &31_300);
+        client.set_quorum(&1);
+        client.set_cpi(&reporters[0], &nga, &50_000);
+        client.pause_feed(&FeedKind::Cpi, &nga);
+
+        // Registered feeds that never published are reported too
+        let kenya = Symbol::new(&env, "KEN");
+        let mut metadata = client.get_feed_metadata(&FeedKind::Cpi, &usa);
+        metadata.base = kenya.clone();
+        client.register_feed(&FeedKind::Cpi, &kenya, &metadata);
+
+        env.ledger().with_mut(|li| li.timestamp = 9_000);
+        let health = client.feed_health(&0, &10);
+        assert_eq!(health.len(), 3);
+
+        let usa_health = health.get_unchecked(0);
+        assert_eq!(usa_health.feed, usa);
+        assert!(!usa_health.never_updated);
+        assert_eq!(usa_health.age, 8_000);
+        assert_eq!(usa_health.missed_heartbeats, 2);
+        assert_eq!(usa_health.last_participants, 2);
+        assert_eq!(usa_health.open_submissions, 1);
+        assert_eq!(usa_health.status, FeedStatus::Active);
+
+        // Without a registered heartbeat no heartbeats are counted as missed
+        let nga_health = health.get_unchecked(1);
+        assert_eq!(nga_health.missed_heartbeats, 0);
+        assert_eq!(nga_health.status, FeedStatus::Paused);
+        assert!(!nga_health.tripped);
+
+        let kenya_health = health.get_unchecked(2);
+        assert_eq!(kenya_health.feed, kenya);
+        assert!(kenya_health.never_updated);
+        assert_eq!(kenya_health.age, u64::MAX);
+
+        // Feeds are reported page by page
+        let page = client.feed_health(&1, &1);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page.get_unchecked(0).feed, nga);
+        assert_eq!(client.feed_health(&2, &u32::MAX).get_unchecked(0).feed, kenya);
+        assert!(client.feed_health(&5, &10).is_empty());
+    }
+
+    #[test]
+    fn test_inflation_math() {
+        let env = Env::default();
+        let (client, _admin, reporter) = setup(&env);
+
+        // 10.25% over six months compounds to 10.25% a year only over a full year
+        assert_eq!(client.annualize_inflation(&10_000, &11_000, &12), 1_000);
+        assert_eq!(client.annualize_inflation(&10_000, &10_500, &6), 1_025);
+        assert_eq!(client.annualize_inflation(&10_000, &12_100, &24), 1_000);
+        assert_eq!(client.annualize_inflation(&10_000, &9_000, &12), -1_000);
+        assert_eq!(
+            client.try_annualize_inflation(&10_000, &11_000, &0),
+            Err(Ok(Error::InvalidPeriod))
+        );
+
+        assert_eq!(client.project_cpi(&10_000, &1_000, &12), 11_000);
+        assert_eq!(client.project_cpi(&10_000, &1_000, &6), 10_488);
+
+        // 8% nominal with 3% inflation is a 4.85% real return
+        assert_eq!(client.real_rate(&800, &300), 485);
+        assert_eq!(client.real_rate(&300, &800), -463);
+
+        let country = Symbol::new(&env, "NGA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &country);
+        client.record_cpi(&reporter, &country, &202401, &1_000);
+        client.record_cpi(&reporter, &country, &202407, &1_100);
+        client.record_cpi(&reporter, &country, &202501, &1_250);
+
+        assert_eq!(client.get_annualized_inflation(&country, &202401, &202407), 2_100);
+        assert_eq!(client.get_real_return(&country, &3_000), 400);
+        assert_eq!(client.deflate(&country, &1_000, &202401, &202501), 1_250);
+        assert_eq!(client.deflate(&country, &1_000, &202501, &202401), 800);
+        assert_eq!(
+            client.try_deflate(&country, &1_000, &202312, &202401),
+            Err(Ok(Error::PeriodNotFound))
+        );
+    }
+
+    #[test]
+    fn test_regional_baskets() {
+        let env = Env::default();
+        let (client, _admin, reporter) = setup(&env);
+
+        let nga = Symbol::new(&env, "NGA");
+        let gha = Symbol::new(&env, "GHA");
+        let wafrica = Symbol::new(&env, "WAFRICA");
+        client.allow_feed(&reporter, &FeedKind::Cpi, &nga);
+        client.allow_feed(&reporter, &FeedKind::Cpi, &gha);
+
+        assert_eq!(
+            client.try_define_basket(&wafrica, &Vec::from_array(&env, [(nga.clone(), 6_000), (gha.clone(), 3_000)])),
+            Err(Ok(Error::InvalidValue.into()))
+        );
+        client.define_basket(&wafrica, &Vec::from_array(&env, [(nga.clone(), 6_000), (gha.clone(), 4_000)]));
+
+        // Published once every component has a value, as old as its stalest component
+        env.ledger().with_mut(|li| li.timestamp = 1_000);
+        client.set_cpi(&reporter, &nga, &500);
+        assert_eq!(client.try_get_cpi(&wafrica), Err(Ok(Error::FeedNotFound)));
+        env.ledger().with_mut(|li| li.timestamp = 2_000);
+        client.set_cpi(&reporter, &gha, &300);
+        assert_eq!(client.get_cpi(&wafrica), 420);
+        assert_eq!(client.get_with_confidence(&FeedKind::Cpi, &wafrica).2, 1_000);
+
+        client.set_cpi(&reporter, &nga, &600);
+        assert_eq!(client.get_cpi(&wafrica), 480);
+        assert_eq!(client.get_with_confidence(&FeedKind::Cpi, &wafrica).2, 2_000);
+
+        // A paused basket is not recomputed until resumed
+        client.pause_feed(&FeedKind::Cpi, &wafrica);
+        client.set_cpi(&reporter, &nga, &700);
+        assert_eq!(client.try_get_cpi(&wafrica), Err(Ok(Error::FeedPaused)));
+        client.resume_feed(&FeedKind::Cpi, &wafrica);
+        assert_eq!(client.get_cpi(&wafrica), 540);
+
+        // Baskets only publish values computed from their components
+        client.allow_feed(&reporter, &FeedKind::Cpi, &wafrica);
+        assert_eq!(
+            client.try_set_cpi(&reporter, &wafrica, &100),
+            Err(Ok(Error::Unauthorized.into()))
+        );
+        assert_eq!(
+            client.try_record_cpi(&reporter, &wafrica, &202_401, &100),
+            Err(Ok(Error::Unauthorized.into()))
+        );
+
+        // A weighted sum that overflows leaves the basket as is
+        client.set_cpi(&reporter, &nga, &(i128::MAX / 2));
+        assert_eq!(client.get_cpi(&nga), i128::MAX / 2);
+        assert_eq!(client.get_cpi(&wafrica), 540);
+        client.set_cpi(&reporter, &nga, &700);
+
+        // Components must declare the same decimals
+        client.register_feed(
+            &FeedKind::Cpi,
+            &gha,
+            &FeedMetadata {
+                decimals: 3,
+                base: gha.clone(),
+                quote: Symbol::new(&env, "GHS"),
+                unit: String::from_str(&env, "CPI index"),
+                source: String::from_str(&env, "GSS"),
+                heartbeat: 0,
+            },
+        );
+        assert_eq!(
+            client.try_define_basket(&wafrica, &Vec::from_array(&env, [(nga.clone(), 6_000), (gha.clone(), 4_000)])),
+            Err(Ok(Error::InvalidDecimals.into()))
+        );
+
+        // A basket cannot be a component of another basket
+        assert_eq!(
+            client.try_define_basket(&Symbol::new(&env, "AFRICA"), &Vec::from_array(&env, [(wafrica.clone(), 10_000)])),
+            Err(Ok(Error::InvalidValue.into()))
+        );
+
+        client.remove_basket(&wafrica);
+        client.set_cpi(&reporter, &gha, &400);
+        assert_eq!(client.get_cpi(&wafrica), 540);
+        assert_eq!(client.try_get_basket(&wafrica), Err(Ok(Error::FeedNotFound)));
+    }
+
+    #[test]
+    fn test_version_and_migrate() {
+        let env = Env::default();
+        let (client, _admin, _reporter) = setup(&env);
+
+        assert_eq!(client.version(), CONTRACT_VERSION);
+        assert_eq!(client.migrate(), CONTRACT_VERSION);
+
+        // An oracle initialized over the single-map layout starts at version 1 until migrated
+        let legacy_client = OracleContractClient::new(&env, &env.register_contract(None, OracleContract));
+        let usa = Symbol::new(&env, "USA");
+        let mut legacy = Map::new(&env);
+        legacy.set(usa.clone(), 305i128);
+        env.as_contract(&legacy_client.address, || {
+            env.storage()
+                .persistent()
+                .set(&Symbol::new(&env, "cpi"), &legacy);
+        });
+        legacy_client.init(&Address::generate(&env));
+        assert_eq!(legacy_client.version(), 1);
+        assert_eq!(legacy_client.try_get_cpi(&usa), Err(Ok(Error::FeedNotFound)));
+
+        assert_eq!(legacy_client.migrate(), CONTRACT_VERSION);
+        assert_eq!(legacy_client.version(), CONTRACT_VERSION);
+        let migrated = legacy_client.get_cpi_with_timestamp(&usa);
+        assert_eq!(migrated.value, 305);
+        assert_eq!(migrated.timestamp, 0);
+
+        // The legacy map is removed, so a second run does nothing
+        assert_eq!(legacy_client.migrate(), CONTRACT_VERSION);
+        env.as_contract(&legacy_client.address, || {
+            assert!(!storage::has_legacy_values(&env));
+        });
     }
 }
@@ -0,0 +1,97 @@
+use crate::Error;
+
+/// Fixed-point scale of intermediate results (18 decimals)
+pub const WAD: i128 = 1_000_000_000_000_000_000;
+
+/// ln(2) at 18 decimals
+const LN_2: i128 = 693_147_180_559_945_309;
+
+/// Largest argument `exp` accepts, keeping results well inside i128
+const MAX_EXP: i128 = 40 * WAD;
+
+/// Series terms after which further terms are below 1e-18
+const SERIES_TERMS: i128 = 40;
+
+/// `a * b / c` rounded to the nearest unit, failing instead of overflowing
+pub fn mul_div(a: i128, b: i128, c: i128) -> Result<i128, Error> {
+    let product = a.checked_mul(b).ok_or(Error::Overflow)?;
+    let half = if (product < 0) != (c < 0) { -c / 2 } else { c / 2 };
+    product
+        .checked_add(half)
+        .map(|rounded| rounded / c)
+        .ok_or(Error::Overflow)
+}
+
+/// Natural logarithm of a positive WAD value
+pub fn ln(x: i128) -> Result<i128, Error> {
+    if x <= 0 {
+        return Err(Error::InvalidValue);
+    }
+
+    // Reduce to y in [1, 2) with x = y * 2^k
+    let mut y = x;
+    let mut k: i128 = 0;
+    while y >= 2 * WAD {
+        y /= 2;
+        k += 1;
+    }
+    while y < WAD {
+        y *= 2;
+        k -= 1;
+    }
+
+    // ln(y) = 2 * atanh(z) with z = (y - 1) / (y + 1), so z <= 1/3
+    let z = (y - WAD) * WAD / (y + WAD);
+    let z_squared = z * z / WAD;
+    let mut term = z;
+    let mut sum = z;
+    let mut n = 3;
+    while n < 2 * SERIES_TERMS && term != 0 {
+        term = term * z_squared / WAD;
+        sum += term / n;
+        n += 2;
+    }
+
+    Ok(k * LN_2 + 2 * sum)
+}
+
+/// e raised to a WAD value
+pub fn exp(x: i128) -> Result<i128, Error> {
+    if x > MAX_EXP {
+        return Err(Error::Overflow);
+    }
+    if x < -MAX_EXP {
+        return Ok(0);
+    }
+
+    // Reduce to r in [-ln2/2, ln2/2] with x = r + k * ln2
+    let k = (x + if x < 0 { -LN_2 / 2 } else { LN_2 / 2 }) / LN_2;
+    let r = x - k * LN_2;
+
+    let mut term = WAD;
+    let mut sum = WAD;
+    let mut n = 1;
+    while n < SERIES_TERMS && term != 0 {
+        term = term * r / WAD / n;
+        sum += term;
+        n += 1;
+    }
+
+    if k >= 0 {
+        sum.checked_mul(1i128 << k).ok_or(Error::Overflow)
+    } else {
+        Ok(sum >> -k)
+    }
+}
+
+/// A positive WAD value raised to the power `numerator / denominator`
+pub fn pow(base: i128, numerator: i128, denominator: i128) -> Result<i128, Error> {
+    let exponent = mul_div(ln(base)?, numerator, denominator)?;
+    exp(exponent)
+}
+
+/// Months from one YYYYMM period to another
+pub fn months_between(from: u32, to: u32) -> i128 {
+    let months = |period: u32| (period / 100) as i128 * 12 + (period % 100) as i128;
+    months(to) - months(from)
+}
@@ -0,0 +1,636 @@
+use soroban_sdk::{
+    contracttype, panic_with_error, Address, BytesN, Env, IntoVal, Map, Symbol, TryFromVal, Val,
+    Vec,
+};
+
+use crate::{
+    CommitRevealConfig, CommitRound, Error, Fallback, FeeConfig, FeedKind, FeedMetadata, FeedStatus,
+    FeedValue, FxConfig, Observation, OptimisticConfig, PriceConfig, Proposal, Round, StakeConfig,
+    Subscription, Unbonding,
+};
+
+const DAY_IN_LEDGERS: u32 = 17_280;
+
+/// Contract-wide settings are kept alive for a week past the last call
+const INSTANCE_TTL_THRESHOLD: u32 = 6 * DAY_IN_LEDGERS;
+const INSTANCE_TTL_EXTEND_TO: u32 = 7 * DAY_IN_LEDGERS;
+
+/// Per-feed and per-reporter entries are kept alive for a month past their last access
+const PERSISTENT_TTL_THRESHOLD: u32 = 29 * DAY_IN_LEDGERS;
+const PERSISTENT_TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+
+/// Default number of reporter submissions that close a round
+const DEFAULT_QUORUM: u32 = 1;
+
+/// Default seconds a round collects submissions before it expires
+const DEFAULT_ROUND_MAX_AGE: u64 = 86_400;
+
+/// Default number of CPI periods kept per country (five years of monthly data)
+const DEFAULT_HISTORY_LIMIT: u32 = 60;
+
+/// Default number of price observations kept per FX and asset feed
+const DEFAULT_OBSERVATION_LIMIT: u32 = 48;
+
+/// Symbol keys of the single-map layout each feed kind was stored under before `DataKey`
+const LEGACY_MAPS: [(FeedKind, &str); 3] = [
+    (FeedKind::Cpi, "cpi"),
+    (FeedKind::Fx, "fx"),
+    (FeedKind::Asset, "assets"),
+];
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    // Instance storage
+    Admin,
+    Quorum,
+    HistoryLimit,
+    RoundMaxAge,
+    ObservationLimit,
+    PriceConfig,
+    OptimisticConfig,
+    StakeConfig,
+    FxConfig,
+    Fallbacks,
+    CommitReveal,
+    FeeConfig,
+    /// Read fees collected and not yet withdrawn
+    FeeBalance,
+    /// Storage version the contract state was last migrated to
+    Version,
+    // Persistent storage
+    /// Feeds that have published a value
+    Feeds,
+    /// Feeds with registered metadata
+    RegisteredFeeds,
+    /// Feeds a reporter may write
+    Reporter(Address),
+    ReporterKey(Address),
+    KeyReporter(BytesN<32>),
+    Nonce(BytesN<32>),
+    Value(FeedKind, Symbol),
+    Round(FeedKind, Symbol),
+    Observations(FeedKind, Symbol),
+    CpiHistory(Symbol),
+    Metadata(FeedKind, Symbol),
+    MaxDeviation(FeedKind, Symbol),
+    Pending(FeedKind, Symbol),
+    /// Asset feed backing a Stellar asset contract
+    AssetFeed(Address),
+    /// Stellar asset contract backed by an asset feed
+    AssetAddress(Symbol),
+    /// Open optimistic CPI proposal of a country
+    Proposal(Symbol),
+    /// Challenger of a disputed CPI proposal
+    Challenger(Symbol),
+    /// Tokens staked by a reporter
+    Stake(Address),
+    /// Stake a reporter has withdrawn that is still unbonding
+    Unbonding(Address),
+    /// Consumer contracts notified of a feed's updates
+    Subscriptions(FeedKind, Symbol),
+    /// Confidence of the published value
+    Confidence(FeedKind, Symbol),
+    /// Confidence of the value held by the circuit breaker
+    PendingConfidence(FeedKind, Symbol),
+    /// Uncertainty stated by reporters in the open round
+    RoundConfidences(FeedKind, Symbol),
+    /// Open commit-reveal round of a feed
+    CommitRound(FeedKind, Symbol),
+    Status(FeedKind, Symbol),
+    /// Feed replacing a deprecated one
+    Successor(FeedKind, Symbol),
+    /// Fee charged per premium read
+    ReadFee(FeedKind, Symbol),
+    /// Consumer exempt from read fees
+    FreeReader(Address),
+    /// Prepaid read fees of a consumer
+    Credit(Address),
+    /// Reporters in the last closed round of a feed
+    Participants(FeedKind, Symbol),
+    /// Weighted country components of a regional CPI index
+    Basket(Symbol),
+    /// Regional CPI indices a country belongs to
+    BasketsOf(Symbol),
+}
+
+fn get<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+    let value = env.storage().persistent().get(key);
+    if value.is_some() {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, PERSISTENT_TTL_THRESHOLD, PERSISTENT_TTL_EXTEND_TO);
+    }
+    value
+}
+
+fn set<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    env.storage().persistent().set(key, value);
+    env.storage()
+        .persistent()
+        .extend_ttl(key, PERSISTENT_TTL_THRESHOLD, PERSISTENT_TTL_EXTEND_TO);
+}
+
+fn remove(env: &Env, key: &DataKey) {
+    env.storage().persistent().remove(key);
+}
+
+fn get_setting<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> Option<V> {
+    let value = env.storage().instance().get(key);
+    if value.is_some() {
+        extend_instance_ttl(env);
+    }
+    value
+}
+
+fn set_setting<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    env.storage().instance().set(key, value);
+    extend_instance_ttl(env);
+}
+
+pub fn extend_instance_ttl(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_TTL_THRESHOLD, INSTANCE_TTL_EXTEND_TO);
+}
+
+pub fn has_admin(env: &Env) -> bool {
+    env.storage().instance().has(&DataKey::Admin)
+}
+
+pub fn read_admin(env: &Env) -> Address {
+    get_setting(env, &DataKey::Admin).unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized))
+}
+
+pub fn write_admin(env: &Env, admin: &Address) {
+    set_setting(env, &DataKey::Admin, admin);
+}
+
+pub fn read_quorum(env: &Env) -> u32 {
+    get_setting(env, &DataKey::Quorum).unwrap_or(DEFAULT_QUORUM)
+}
+
+pub fn write_quorum(env: &Env, quorum: u32) {
+    set_setting(env, &DataKey::Quorum, &quorum);
+}
+
+pub fn read_round_max_age(env: &Env) -> u64 {
+    get_setting(env, &DataKey::RoundMaxAge).unwrap_or(DEFAULT_ROUND_MAX_AGE)
+}
+
+pub fn write_round_max_age(env: &Env, max_age: u64) {
+    set_setting(env, &DataKey::RoundMaxAge, &max_age);
+}
+
+pub fn read_history_limit(env: &Env) -> u32 {
+    get_setting(env, &DataKey::HistoryLimit).unwrap_or(DEFAULT_HISTORY_LIMIT)
+}
+
+pub fn write_history_limit(env: &Env, limit: u32) {
+    set_setting(env, &DataKey::HistoryLimit, &limit);
+}
+
+pub fn read_observation_limit(env: &Env) -> u32 {
+    get_setting(env, &DataKey::ObservationLimit).unwrap_or(DEFAULT_OBSERVATION_LIMIT)
+}
+
+pub fn write_observation_limit(env: &Env, limit: u32) {
+    set_setting(env, &DataKey::ObservationLimit, &limit);
+}
+
+pub fn read_price_config(env: &Env) -> Option<PriceConfig> {
+    get_setting(env, &DataKey::PriceConfig)
+}
+
+pub fn write_price_config(env: &Env, config: &PriceConfig) {
+    set_setting(env, &DataKey::PriceConfig, config);
+}
+
+pub fn read_optimistic_config(env: &Env) -> Option<OptimisticConfig> {
+    get_setting(env, &DataKey::OptimisticConfig)
+}
+
+pub fn write_optimistic_config(env: &Env, config: &OptimisticConfig) {
+    set_setting(env, &DataKey::OptimisticConfig, config);
+}
+
+pub fn read_stake_config(env: &Env) -> Option<StakeConfig> {
+    get_setting(env, &DataKey::StakeConfig)
+}
+
+pub fn write_stake_config(env: &Env, config: &StakeConfig) {
+    set_setting(env, &DataKey::StakeConfig, config);
+}
+
+pub fn read_fx_config(env: &Env) -> Option<FxConfig> {
+    get_setting(env, &DataKey::FxConfig)
+}
+
+pub fn write_fx_config(env: &Env, config: &FxConfig) {
+    set_setting(env, &DataKey::FxConfig, config);
+}
+
+pub fn read_fallbacks(env: &Env) -> Vec<Fallback> {
+    get_setting(env, &DataKey::Fallbacks).unwrap_or(Vec::new(env))
+}
+
+pub fn write_fallbacks(env: &Env, fallbacks: &Vec<Fallback>) {
+    set_setting(env, &DataKey::Fallbacks, fallbacks);
+}
+
+pub fn read_commit_reveal_config(env: &Env) -> Option<CommitRevealConfig> {
+    get_setting(env, &DataKey::CommitReveal)
+}
+
+pub fn write_commit_reveal_config(env: &Env, config: &CommitRevealConfig) {
+    set_setting(env, &DataKey::CommitReveal, config);
+}
+
+pub fn remove_commit_reveal_config(env: &Env) {
+    env.storage().instance().remove(&DataKey::CommitReveal);
+}
+
+pub fn read_fee_config(env: &Env) -> Option<FeeConfig> {
+    get_setting(env, &DataKey::FeeConfig)
+}
+
+pub fn write_fee_config(env: &Env, config: &FeeConfig) {
+    set_setting(env, &DataKey::FeeConfig, config);
+}
+
+pub fn read_fee_balance(env: &Env) -> i128 {
+    get_setting(env, &DataKey::FeeBalance).unwrap_or(0)
+}
+
+pub fn write_fee_balance(env: &Env, balance: i128) {
+    set_setting(env, &DataKey::FeeBalance, &balance);
+}
+
+/// Storage version of the contract state; 1 for deployments that predate versioning
+pub fn read_version(env: &Env) -> u32 {
+    get_setting(env, &DataKey::Version).unwrap_or(1)
+}
+
+pub fn write_version(env: &Env, version: u32) {
+    set_setting(env, &DataKey::Version, &version);
+}
+
+/// Feeds that have published a value, in first-publication order
+pub fn read_feeds(env: &Env) -> Vec<(FeedKind, Symbol)> {
+    get(env, &DataKey::Feeds).unwrap_or(Vec::new(env))
+}
+
+/// Feeds with registered metadata, in registration order
+pub fn read_registered_feeds(env: &Env) -> Vec<(FeedKind, Symbol)> {
+    get(env, &DataKey::RegisteredFeeds).unwrap_or(Vec::new(env))
+}
+
+fn add_feed(env: &Env, kind: FeedKind, feed: &Symbol) {
+    let mut feeds = read_feeds(env);
+    if !feeds.contains((kind, feed.clone())) {
+        feeds.push_back((kind, feed.clone()));
+        set(env, &DataKey::Feeds, &feeds);
+    }
+}
+
+pub fn read_reporter_feeds(env: &Env, reporter: &Address) -> Option<Vec<(FeedKind, Symbol)>> {
+    get(env, &DataKey::Reporter(reporter.clone()))
+}
+
+pub fn write_reporter_feeds(env: &Env, reporter: &Address, feeds: &Vec<(FeedKind, Symbol)>) {
+    set(env, &DataKey::Reporter(reporter.clone()), feeds);
+}
+
+pub fn remove_reporter(env: &Env, reporter: &Address) {
+    remove(env, &DataKey::Reporter(reporter.clone()));
+}
+
+pub fn read_key_reporter(env: &Env, public_key: &BytesN<32>) -> Option<Address> {
+    get(env, &DataKey::KeyReporter(public_key.clone()))
+}
+
+/// Register the reporter's signing key, dropping the one it replaces
+pub fn write_reporter_key(env: &Env, reporter: &Address, public_key: &BytesN<32>) {
+    remove_reporter_key(env, reporter);
+    set(env, &DataKey::ReporterKey(reporter.clone()), public_key);
+    set(env, &DataKey::KeyReporter(public_key.clone()), reporter);
+}
+
+pub fn remove_reporter_key(env: &Env, reporter: &Address) {
+    let key = DataKey::ReporterKey(reporter.clone());
+    if let Some(public_key) = get::<BytesN<32>>(env, &key) {
+        remove(env, &DataKey::KeyReporter(public_key));
+        remove(env, &key);
+    }
+}
+
+pub fn read_nonce(env: &Env, public_key: &BytesN<32>) -> u64 {
+    get(env, &DataKey::Nonce(public_key.clone())).unwrap_or(0)
+}
+
+pub fn write_nonce(env: &Env, public_key: &BytesN<32>, nonce: u64) {
+    set(env, &DataKey::Nonce(public_key.clone()), &nonce);
+}
+
+pub fn read_value(env: &Env, kind: FeedKind, feed: &Symbol) -> Option<FeedValue> {
+    get(env, &DataKey::Value(kind, feed.clone()))
+}
+
+pub fn write_value(env: &Env, kind: FeedKind, feed: &Symbol, value: &FeedValue) {
+    // The feed index is only touched by a feed's first value, keeping it off the publish path
+    if !env.storage().persistent().has(&DataKey::Value(kind, feed.clone())) {
+        add_feed(env, kind, feed);
+    }
+    set(env, &DataKey::Value(kind, feed.clone()), value);
+}
+
+pub fn read_round(env: &Env, kind: FeedKind, feed: &Symbol) -> Round {
+    get(env, &DataKey::Round(kind, feed.clone())).unwrap_or(Round::new(env, 0))
+}
+
+pub fn write_round(env: &Env, kind: FeedKind, feed: &Symbol, round: &Round) {
+    set(env, &DataKey::Round(kind, feed.clone()), round);
+}
+
+pub fn read_observations(env: &Env, kind: FeedKind, feed: &Symbol) -> Option<Vec<Observation>> {
+    get(env, &DataKey::Observations(kind, feed.clone()))
+}
+
+pub fn write_observations(env: &Env, kind: FeedKind, feed: &Symbol, buffer: &Vec<Observation>) {
+    set(env, &DataKey::Observations(kind, feed.clone()), buffer);
+}
+
+pub fn read_cpi_history(env: &Env, country: &Symbol) -> Map<u32, i128> {
+    get(env, &DataKey::CpiHistory(country.clone())).unwrap_or(Map::new(env))
+}
+
+pub fn write_cpi_history(env: &Env, country: &Symbol, periods: &Map<u32, i128>) {
+    set(env, &DataKey::CpiHistory(country.clone()), periods);
+}
+
+pub fn read_metadata(env: &Env, kind: FeedKind, feed: &Symbol) -> Option<FeedMetadata> {
+    get(env, &DataKey::Metadata(kind, feed.clone()))
+}
+
+pub fn write_metadata(env: &Env, kind: FeedKind, feed: &Symbol, metadata: &FeedMetadata) {
+    if !env.storage().persistent().has(&DataKey::Metadata(kind, feed.clone())) {
+        let mut feeds = read_registered_feeds(env);
+        feeds.push_back((kind, feed.clone()));
+        set(env, &DataKey::RegisteredFeeds, &feeds);
+    }
+    set(env, &DataKey::Metadata(kind, feed.clone()), metadata);
+}
+
+pub fn read_max_deviation(env: &Env, kind: FeedKind, feed: &Symbol) -> u32 {
+    get(env, &DataKey::MaxDeviation(kind, feed.clone())).unwrap_or(0)
+}
+
+pub fn write_max_deviation(env: &Env, kind: FeedKind, feed: &Symbol, max_bps: u32) {
+    set(env, &DataKey::MaxDeviation(kind, feed.clone()), &max_bps);
+}
+
+pub fn read_pending(env: &Env, kind: FeedKind, feed: &Symbol) -> Option<FeedValue> {
+    get(env, &DataKey::Pending(kind, feed.clone()))
+}
+
+pub fn write_pending(env: &Env, kind: FeedKind, feed: &Symbol, value: &FeedValue) {
+    set(env, &DataKey::Pending(kind, feed.clone()), value);
+}
+
+pub fn remove_pending(env: &Env, kind: FeedKind, feed: &Symbol) {
+    remove(env, &DataKey::Pending(kind, feed.clone()));
+}
+
+pub fn read_confidence(env: &Env, kind: FeedKind, feed: &Symbol) -> i128 {
+    get(env, &DataKey::Confidence(kind, feed.clone())).unwrap_or(0)
+}
+
+pub fn write_confidence(env: &Env, kind: FeedKind, feed: &Symbol, confidence: i128) {
+    set(env, &DataKey::Confidence(kind, feed.clone()), &confidence);
+}
+
+pub fn read_pending_confidence(env: &Env, kind: FeedKind, feed: &Symbol) -> i128 {
+    get(env, &DataKey::PendingConfidence(kind, feed.clone())).unwrap_or(0)
+}
+
+pub fn write_pending_confidence(env: &Env, kind: FeedKind, feed: &Symbol, confidence: i128) {
+    set(env, &DataKey::PendingConfidence(kind, feed.clone()), &confidence);
+}
+
+pub fn remove_pending_confidence(env: &Env, kind: FeedKind, feed: &Symbol) {
+    remove(env, &DataKey::PendingConfidence(kind, feed.clone()));
+}
+
+pub fn read_round_confidences(env: &Env, kind: FeedKind, feed: &Symbol) -> Map<Address, i128> {
+    get(env, &DataKey::RoundConfidences(kind, feed.clone())).unwrap_or(Map::new(env))
+}
+
+pub fn write_round_confidences(env: &Env, kind: FeedKind, feed: &Symbol, confidences: &Map<Address, i128>) {
+    let key = DataKey::RoundConfidences(kind, feed.clone());
+    if confidences.is_empty() {
+        remove(env, &key);
+    } else {
+        set(env, &key, confidences);
+    }
+}
+
+pub fn read_commit_round(env: &Env, kind: FeedKind, feed: &Symbol) -> Option<CommitRound> {
+    get(env, &DataKey::CommitRound(kind, feed.clone()))
+}
+
+pub fn write_commit_round(env: &Env, kind: FeedKind, feed: &Symbol, round: &CommitRound) {
+    set(env, &DataKey::CommitRound(kind, feed.clone()), round);
+}
+
+pub fn remove_commit_round(env: &Env, kind: FeedKind, feed: &Symbol) {
+    remove(env, &DataKey::CommitRound(kind, feed.clone()));
+}
+
+pub fn read_feed_status(env: &Env, kind: FeedKind, feed: &Symbol) -> FeedStatus {
+    get(env, &DataKey::Status(kind, feed.clone())).unwrap_or(FeedStatus::Active)
+}
+
+pub fn write_feed_status(env: &Env, kind: FeedKind, feed: &Symbol, status: FeedStatus) {
+    set(env, &DataKey::Status(kind, feed.clone()), &status);
+}
+
+pub fn read_successor(env: &Env, kind: FeedKind, feed: &Symbol) -> Option<Symbol> {
+    get(env, &DataKey::Successor(kind, feed.clone()))
+}
+
+pub fn write_successor(env: &Env, kind: FeedKind, feed: &Symbol, successor: &Symbol) {
+    set(env, &DataKey::Successor(kind, feed.clone()), successor);
+}
+
+pub fn remove_successor(env: &Env, kind: FeedKind, feed: &Symbol) {
+    remove(env, &DataKey::Successor(kind, feed.clone()));
+}
+
+pub fn read_read_fee(env: &Env, kind: FeedKind, feed: &Symbol) -> i128 {
+    get(env, &DataKey::ReadFee(kind, feed.clone())).unwrap_or(0)
+}
+
+pub fn write_read_fee(env: &Env, kind: FeedKind, feed: &Symbol, fee: i128) {
+    let key = DataKey::ReadFee(kind, feed.clone());
+    if fee == 0 {
+        remove(env, &key);
+    } else {
+        set(env, &key, &fee);
+    }
+}
+
+pub fn read_free_reader(env: &Env, consumer: &Address) -> bool {
+    get(env, &DataKey::FreeReader(consumer.clone())).unwrap_or(false)
+}
+
+pub fn write_free_reader(env: &Env, consumer: &Address, free: bool) {
+    let key = DataKey::FreeReader(consumer.clone());
+    if free {
+        set(env, &key, &true);
+    } else {
+        remove(env, &key);
+    }
+}
+
+pub fn read_credit(env: &Env, consumer: &Address) -> i128 {
+    get(env, &DataKey::Credit(consumer.clone())).unwrap_or(0)
+}
+
+pub fn write_credit(env: &Env, consumer: &Address, credit: i128) {
+    set(env, &DataKey::Credit(consumer.clone()), &credit);
+}
+
+pub fn read_participants(env: &Env, kind: FeedKind, feed: &Symbol) -> u32 {
+    get(env, &DataKey::Participants(kind, feed.clone())).unwrap_or(0)
+}
+
+pub fn write_participants(env: &Env, kind: FeedKind, feed: &Symbol, participants: u32) {
+    set(env, &DataKey::Participants(kind, feed.clone()), &participants);
+}
+
+pub fn read_basket(env: &Env, basket: &Symbol) -> Option<Vec<(Symbol, u32)>> {
+    get(env, &DataKey::Basket(basket.clone()))
+}
+
+pub fn write_basket(env: &Env, basket: &Symbol, components: &Vec<(Symbol, u32)>) {
+    set(env, &DataKey::Basket(basket.clone()), components);
+}
+
+pub fn remove_basket(env: &Env, basket: &Symbol) {
+    remove(env, &DataKey::Basket(basket.clone()));
+}
+
+pub fn read_baskets_of(env: &Env, country: &Symbol) -> Vec<Symbol> {
+    get(env, &DataKey::BasketsOf(country.clone())).unwrap_or(Vec::new(env))
+}
+
+pub fn write_baskets_of(env: &Env, country: &Symbol, baskets: &Vec<Symbol>) {
+    set(env, &DataKey::BasketsOf(country.clone()), baskets);
+}
+
+pub fn read_asset_feed(env: &Env, address: &Address) -> Option<Symbol> {
+    get(env, &DataKey::AssetFeed(address.clone()))
+}
+
+pub fn read_asset_address(env: &Env, asset: &Symbol) -> Option<Address> {
+    get(env, &DataKey::AssetAddress(asset.clone()))
+}
+
+pub fn write_asset_address(env: &Env, address: &Address, asset: &Symbol) {
+    set(env, &DataKey::AssetFeed(address.clone()), asset);
+    set(env, &DataKey::AssetAddress(asset.clone()), address);
+}
+
+pub fn read_proposal(env: &Env, country: &Symbol) -> Option<Proposal> {
+    get(env, &DataKey::Proposal(country.clone()))
+}
+
+pub fn write_proposal(env: &Env, country: &Symbol, proposal: &Proposal) {
+    set(env, &DataKey::Proposal(country.clone()), proposal);
+}
+
+pub fn remove_proposal(env: &Env, country: &Symbol) {
+    remove(env, &DataKey::Proposal(country.clone()));
+}
+
+pub fn read_challenger(env: &Env, country: &Symbol) -> Option<Address> {
+    get(env, &DataKey::Challenger(country.clone()))
+}
+
+pub fn write_challenger(env: &Env, country: &Symbol, challenger: &Address) {
+    set(env, &DataKey::Challenger(country.clone()), challenger);
+}
+
+pub fn remove_challenger(env: &Env, country: &Symbol) {
+    remove(env, &DataKey::Challenger(country.clone()));
+}
+
+pub fn read_stake(env: &Env, reporter: &Address) -> i128 {
+    get(env, &DataKey::Stake(reporter.clone())).unwrap_or(0)
+}
+
+pub fn write_stake(env: &Env, reporter: &Address, amount: i128) {
+    set(env, &DataKey::Stake(reporter.clone()), &amount);
+}
+
+pub fn read_unbonding(env: &Env, reporter: &Address) -> Unbonding {
+    get(env, &DataKey::Unbonding(reporter.clone())).unwrap_or(Unbonding {
+        amount: 0,
+        available_at: 0,
+    })
+}
+
+pub fn write_unbonding(env: &Env, reporter: &Address, unbonding: &Unbonding) {
+    set(env, &DataKey::Unbonding(reporter.clone()), unbonding);
+}
+
+pub fn remove_unbonding(env: &Env, reporter: &Address) {
+    remove(env, &DataKey::Unbonding(reporter.clone()));
+}
+
+pub fn read_subscriptions(env: &Env, kind: FeedKind, feed: &Symbol) -> Vec<Subscription> {
+    get(env, &DataKey::Subscriptions(kind, feed.clone())).unwrap_or(Vec::new(env))
+}
+
+pub fn write_subscriptions(env: &Env, kind: FeedKind, feed: &Symbol, subscriptions: &Vec<Subscription>) {
+    set(env, &DataKey::Subscriptions(kind, feed.clone()), subscriptions);
+}
+
+/// Whether any values are still stored in the pre-`DataKey` layout
+pub fn has_legacy_values(env: &Env) -> bool {
+    LEGACY_MAPS
+        .iter()
+        .any(|(_, legacy_key)| env.storage().persistent().has(&Symbol::new(env, legacy_key)))
+}
+
+/// Move values from the pre-`DataKey` layout, where each feed kind was one
+/// `Map<Symbol, i128>` under a symbol key, into per-feed entries.
+/// Values already present in the new layout are newer and are kept.
+/// Migrated values carry a zero timestamp since their age is unknown.
+pub fn migrate_legacy_values(env: &Env) -> u32 {
+    let mut migrated = 0;
+
+    for (kind, legacy_key) in LEGACY_MAPS {
+        let legacy_key = Symbol::new(env, legacy_key);
+        let legacy: Option<Map<Symbol, i128>> = env.storage().persistent().get(&legacy_key);
+        let Some(legacy) = legacy else {
+            continue;
+        };
+
+        for (feed, value) in legacy.iter() {
+            if read_value(env, kind, &feed).is_none() {
+                let value = FeedValue {
+                    value,
+                    timestamp: 0,
+                    sequence: 0,
+                };
+                write_value(env, kind, &feed, &value);
+                migrated += 1;
+            }
+        }
+
+        env.storage().persistent().remove(&legacy_key);
+    }
+
+    migrated
+}
@@ -1,4 +1,22 @@
-use soroban_sdk::{contract, contractimpl, Address, Env, Map, Symbol, token};
+use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, IntoVal, Map, Symbol, token};
+
+/// Feed categories of the Aureus oracle
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FeedKind {
+    Cpi,
+    Fx,
+    Asset,
+}
+
+/// Feed value returned by the Aureus oracle's `read_premium`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeedValue {
+    pub value: i128,
+    pub timestamp: u64,
+    pub sequence: u32,
+}
 
 #[contract]
 pub struct SavingsContract;
@@ -164,12 +182,14 @@ impl SavingsContract {
 
         let country = user_countries.get(user.clone()).unwrap_or(Symbol::new(&env, "USA"));
 
-        // Call oracle to get CPI
-        let cpi: i128 = env.invoke_contract(
-            &oracle_address,
-            &Symbol::new(&env, "get_cpi"),
-            (country,).into_val(&env),
-        );
+        // Call oracle to get CPI through read_premium
+        let cpi = env
+            .invoke_contract::<FeedValue>(
+                &oracle_address,
+                &Symbol::new(&env, "read_premium"),
+                (env.current_contract_address(), FeedKind::Cpi, country).into_val(&env),
+            )
+            .value;
 
         // Threshold for high inflation
         let threshold = 200; // e.g., 2.00
@@ -106,7 +106,10 @@ echo ""
 echo "🔧 Sample initialization commands:"
 echo ""
 echo "# Initialize Oracle with sample data"
-echo "stellar contract invoke --id $ORACLE_ID --source $SOURCE_ACCOUNT --network $NETWORK -- set_cpi --country USA --cpi 320"
+echo "stellar contract invoke --id $ORACLE_ID --source $SOURCE_ACCOUNT --network $NETWORK -- init --admin <ADMIN>"
+echo "stellar contract invoke --id $ORACLE_ID --source $SOURCE_ACCOUNT --network $NETWORK -- add_reporter --reporter <REPORTER>"
+echo "stellar contract invoke --id $ORACLE_ID --source $SOURCE_ACCOUNT --network $NETWORK -- allow_feed --reporter <REPORTER> --kind Cpi --feed USA"
+echo "stellar contract invoke --id $ORACLE_ID --source $SOURCE_ACCOUNT --network $NETWORK -- set_cpi --reporter <REPORTER> --country USA --cpi 320"
 echo ""
 echo "# Initialize Savings Contract"  
 echo "stellar contract invoke --id $SAVINGS_ID --source $SOURCE_ACCOUNT --network $NETWORK -- init --token_address <USDC_TOKEN> --oracle_address $ORACLE_ID"